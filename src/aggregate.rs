@@ -0,0 +1,228 @@
+//! Summaries over values
+//!
+//! Values have no type, so these follow `find_int_in_range`: a value is an
+//! integer when it is UTF-8 text that parses as an `i64` once trimmed, and
+//! the numeric summaries skip every other value. Each call scans both tiers.
+
+use crate::error::Result;
+use crate::BurrowDB;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Names `type_histogram` sorts values into, in order of precedence
+pub const VALUE_TYPES: [&str; 5] = ["integer", "float", "boolean", "string", "binary"];
+
+/// How `fold_values` combines values into one result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reducer {
+    /// Every value joined end to end, in key order
+    Concat,
+    /// Sum of the integer values, saturating as `sum_values` does
+    Sum,
+    /// How many values there are
+    Count,
+    /// Length in bytes of the longest value
+    MaxLen,
+}
+
+impl FromStr for Reducer {
+    type Err = String;
+
+    /// Parse a reducer name (concat, sum, count or max-len), ignoring case
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "concat" => Ok(Reducer::Concat),
+            "sum" => Ok(Reducer::Sum),
+            "count" => Ok(Reducer::Count),
+            "max-len" => Ok(Reducer::MaxLen),
+            _ => Err(format!(
+                "unknown reducer '{}' (expected concat, sum, count or max-len)",
+                s
+            )),
+        }
+    }
+}
+
+/// Which of `VALUE_TYPES` a stored value looks like
+pub(crate) fn value_type(value: &[u8]) -> &'static str {
+    let Ok(text) = std::str::from_utf8(value) else {
+        return "binary";
+    };
+    let text = text.trim();
+    if text.parse::<i64>().is_ok() {
+        "integer"
+    } else if text.parse::<f64>().is_ok_and(f64::is_finite) {
+        "float"
+    } else if text == "true" || text == "false" {
+        "boolean"
+    } else {
+        "string"
+    }
+}
+
+/// Parse a stored value as an integer, if it is one
+pub(crate) fn parse_int(value: &[u8]) -> Option<i64> {
+    std::str::from_utf8(value).ok()?.trim().parse().ok()
+}
+
+impl BurrowDB {
+    /// Sum of every integer value, saturating at the `i64` bounds
+    ///
+    /// Zero when there are no integer values.
+    pub fn sum_values(&self) -> Result<i64> {
+        let sum: i128 = self.int_values()?.iter().map(|&n| i128::from(n)).sum();
+        Ok(sum.clamp(i64::MIN.into(), i64::MAX.into()) as i64)
+    }
+
+    /// Mean of every integer value, or `None` if there are none
+    pub fn avg_values(&self) -> Result<Option<f64>> {
+        let values = self.int_values()?;
+        if values.is_empty() {
+            return Ok(None);
+        }
+        let sum: i128 = values.iter().map(|&n| i128::from(n)).sum();
+        Ok(Some(sum as f64 / values.len() as f64))
+    }
+
+    /// Smallest and largest integer values, or `None` if there are none
+    pub fn min_max_values(&self) -> Result<Option<(i64, i64)>> {
+        let values = self.int_values()?;
+        let min = values.iter().min();
+        let max = values.iter().max();
+        Ok(min.zip(max).map(|(&min, &max)| (min, max)))
+    }
+
+    /// How many values are integers
+    pub fn count_int_values(&self) -> Result<usize> {
+        Ok(self.int_values()?.len())
+    }
+
+    /// How many values look like each of `VALUE_TYPES`
+    ///
+    /// A value counts once, as the first type it fits: trimmed text is an
+    /// integer if it parses as an `i64`, then a float if it parses as a
+    /// finite `f64`, then a boolean if it is `true` or `false`; any other
+    /// UTF-8 is a string and the rest is binary. Types with no values are
+    /// left out.
+    pub fn type_histogram(&self) -> Result<HashMap<&'static str, usize>> {
+        let mut histogram = HashMap::new();
+        self.for_each_entry(|_, value| {
+            *histogram.entry(value_type(value)).or_insert(0) += 1;
+            Ok(())
+        })?;
+        Ok(histogram)
+    }
+
+    /// Combine every value with `reducer`, giving the result as text
+    pub fn fold_values(&self, reducer: Reducer) -> Result<String> {
+        self.fold_values_filtered(reducer, |_, _| true)
+    }
+
+    /// Combine the values accepted by `pred` with `reducer`
+    ///
+    /// `pred` is called with each key and its value. Values that aren't
+    /// UTF-8 are concatenated with U+FFFD in place of invalid bytes.
+    pub fn fold_values_filtered<F>(&self, reducer: Reducer, pred: F) -> Result<String>
+    where
+        F: Fn(&str, &[u8]) -> bool,
+    {
+        let mut values = Vec::new();
+        self.for_each_entry(|key, value| {
+            if pred(key, value) {
+                values.push((key.to_string(), value.to_vec()));
+            }
+            Ok(())
+        })?;
+
+        Ok(match reducer {
+            Reducer::Concat => {
+                values.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+                let blob: Vec<u8> = values.into_iter().flat_map(|(_, value)| value).collect();
+                String::from_utf8_lossy(&blob).into_owned()
+            }
+            Reducer::Sum => {
+                let ints = values.iter().filter_map(|(_, value)| parse_int(value));
+                let sum: i128 = ints.map(i128::from).sum();
+                (sum.clamp(i64::MIN.into(), i64::MAX.into()) as i64).to_string()
+            }
+            Reducer::Count => values.len().to_string(),
+            Reducer::MaxLen => values.iter().map(|(_, v)| v.len()).max().unwrap_or(0).to_string(),
+        })
+    }
+
+    fn int_values(&self) -> Result<Vec<i64>> {
+        let mut values = Vec::new();
+        self.for_each_entry(|_, value| {
+            values.extend(parse_int(value));
+            Ok(())
+        })?;
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_aggregates_skip_non_integers() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 2).unwrap();
+        assert_eq!(db.sum_values().unwrap(), 0);
+        assert_eq!(db.avg_values().unwrap(), None);
+        assert_eq!(db.min_max_values().unwrap(), None);
+
+        for (key, value) in [("a", " 10 "), ("b", "-4"), ("c", "text"), ("d", "3")] {
+            db.put_raw(key.to_string(), value.as_bytes().to_vec()).unwrap();
+        }
+
+        assert_eq!(db.count_int_values().unwrap(), 3);
+        assert_eq!(db.sum_values().unwrap(), 9);
+        assert_eq!(db.avg_values().unwrap(), Some(3.0));
+        assert_eq!(db.min_max_values().unwrap(), Some((-4, 10)));
+
+        db.put_raw("e".to_string(), i64::MAX.to_string().into_bytes()).unwrap();
+        db.put_raw("f".to_string(), i64::MAX.to_string().into_bytes()).unwrap();
+        assert_eq!(db.sum_values().unwrap(), i64::MAX);
+    }
+
+    #[test]
+    fn test_fold_values() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 2).unwrap();
+        let entries = [("log.2", "b"), ("log.1", "a"), ("n.1", "5"), ("n.2", "-2"), ("x", "long")];
+        for (key, value) in entries {
+            db.put_raw(key.to_string(), value.as_bytes().to_vec()).unwrap();
+        }
+
+        let logs = |key: &str, _: &[u8]| key.starts_with("log.");
+        assert_eq!(db.fold_values_filtered(Reducer::Concat, logs).unwrap(), "ab");
+        assert_eq!(db.fold_values(Reducer::Sum).unwrap(), "3");
+        assert_eq!(db.fold_values(Reducer::Count).unwrap(), "5");
+        assert_eq!(db.fold_values(Reducer::MaxLen).unwrap(), "4");
+        assert_eq!(db.fold_values_filtered(Reducer::MaxLen, |_, _| false).unwrap(), "0");
+
+        assert_eq!("MAX-LEN".parse(), Ok(Reducer::MaxLen));
+        assert!("avg".parse::<Reducer>().is_err());
+    }
+
+    #[test]
+    fn test_type_histogram() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 2).unwrap();
+        let values: [&[u8]; 7] = [b"007", b"-1", b"2.5", b"true", b"NaN", b"", &[0xff, 0xfe]];
+        for (i, value) in values.into_iter().enumerate() {
+            db.put_raw(format!("k{}", i), value.to_vec()).unwrap();
+        }
+
+        let histogram = db.type_histogram().unwrap();
+        assert_eq!(histogram.len(), 5);
+        assert_eq!(histogram["integer"], 2);
+        assert_eq!(histogram["float"], 1);
+        assert_eq!(histogram["boolean"], 1);
+        assert_eq!(histogram["string"], 2);
+        assert_eq!(histogram["binary"], 1);
+        assert_eq!(db.get("k0").unwrap().unwrap(), b"007");
+    }
+}
@@ -0,0 +1,143 @@
+//! A bounded, in-memory trail of recent operations
+//!
+//! Meant for inspection and debugging, not recovery: entries are never
+//! written to disk, and once the buffer is full each new entry pushes out
+//! the oldest, so memory stays bounded however long the database runs.
+//! Reads are only recorded when asked for, since they usually outnumber
+//! writes.
+
+use crate::BurrowDB;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::SystemTime;
+
+/// The kind of operation an `AuditEntry` records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOp {
+    /// A value was stored or modified
+    Put,
+    /// A key was read with `get` (only recorded if reads are audited)
+    Get,
+    /// A key was removed by `delete`
+    Delete,
+    /// A key was removed because its TTL ran out
+    Expire,
+}
+
+impl fmt::Display for AuditOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuditOp::Put => "put",
+            AuditOp::Get => "get",
+            AuditOp::Delete => "delete",
+            AuditOp::Expire => "expire",
+        };
+        f.pad(name)
+    }
+}
+
+/// One operation in the audit log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// When the operation happened
+    pub at: SystemTime,
+    /// What was done
+    pub op: AuditOp,
+    /// The (normalized) key it was done to
+    pub key: String,
+}
+
+/// The most recent entries, oldest first, holding at most `capacity`
+pub(crate) struct AuditLog {
+    entries: VecDeque<AuditEntry>,
+    capacity: usize,
+    include_reads: bool,
+}
+
+impl BurrowDB {
+    /// Keep the last `capacity` operations in memory for `audit_log`
+    ///
+    /// Puts, deletes and expiries are always recorded; `get` calls only if
+    /// `include_reads` is set. Shrinking the capacity keeps the newest
+    /// entries, and a capacity of 0 turns the log off and clears it.
+    pub fn set_audit_log(&mut self, capacity: usize, include_reads: bool) {
+        if capacity == 0 {
+            self.audit = None;
+            return;
+        }
+
+        let mut entries = self.audit.take().map(|log| log.entries).unwrap_or_default();
+        while entries.len() > capacity {
+            entries.pop_front();
+        }
+        self.audit = Some(AuditLog { entries, capacity, include_reads });
+    }
+
+    /// The recorded operations, oldest first
+    ///
+    /// Empty unless `set_audit_log` has turned the log on.
+    pub fn audit_log(&self) -> Vec<AuditEntry> {
+        match &self.audit {
+            Some(log) => log.entries.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Record `op` on a normalized key, if the audit log wants it
+    pub(crate) fn audit(&mut self, op: AuditOp, key: &str) {
+        let Some(log) = &mut self.audit else {
+            return;
+        };
+        if op == AuditOp::Get && !log.include_reads {
+            return;
+        }
+
+        if log.entries.len() == log.capacity {
+            log.entries.pop_front();
+        }
+        log.entries.push_back(AuditEntry { at: SystemTime::now(), op, key: key.to_string() });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn ops(db: &BurrowDB) -> Vec<(AuditOp, String)> {
+        db.audit_log().into_iter().map(|entry| (entry.op, entry.key)).collect()
+    }
+
+    #[test]
+    fn test_audit_log_keeps_the_newest_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.put_raw("before".to_string(), b"1".to_vec()).unwrap();
+        assert!(db.audit_log().is_empty());
+
+        db.set_audit_log(3, false);
+        db.put_raw("a".to_string(), b"1".to_vec()).unwrap();
+        db.get("a").unwrap();
+        db.delete("a").unwrap();
+        db.put_raw("b".to_string(), b"2".to_vec()).unwrap();
+        db.expire("b", Duration::ZERO);
+        db.flush_expired().unwrap();
+        assert_eq!(ops(&db), vec![
+            (AuditOp::Delete, "a".to_string()),
+            (AuditOp::Put, "b".to_string()),
+            (AuditOp::Expire, "b".to_string()),
+        ]);
+
+        db.set_audit_log(2, true);
+        db.get("missing").unwrap();
+        assert_eq!(ops(&db), vec![
+            (AuditOp::Expire, "b".to_string()),
+            (AuditOp::Get, "missing".to_string()),
+        ]);
+
+        db.set_audit_log(0, true);
+        db.put_raw("c".to_string(), b"3".to_vec()).unwrap();
+        assert!(db.audit_log().is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+use crate::error::Result;
+use crate::links::LinkIndex;
+use crate::lock::DirLock;
+use crate::logging::LogLevel;
+use crate::storage::Storage;
+use crate::tags::TagIndex;
+use crate::transforms::builtin_transforms;
+use crate::BurrowDB;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+
+/// Chainable configuration for a `BurrowDB`
+///
+/// ```no_run
+/// use burrow_db::BurrowDB;
+///
+/// let db = BurrowDB::builder()
+///     .data_dir("./auction_data")
+///     .capacity(10_000)
+///     .max_entries(50_000)
+///     .build()?;
+/// # Ok::<(), burrow_db::BurrowError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct BurrowDBBuilder {
+    data_dir: String,
+    capacity: usize,
+    max_entries: usize,
+    exclusive: bool,
+}
+
+impl Default for BurrowDBBuilder {
+    fn default() -> Self {
+        Self {
+            data_dir: "./data".to_string(),
+            capacity: 0,
+            max_entries: 1000,
+            exclusive: false,
+        }
+    }
+}
+
+impl BurrowDBBuilder {
+    /// Directory for cold tier storage (default "./data")
+    pub fn data_dir(mut self, data_dir: &str) -> Self {
+        self.data_dir = data_dir.to_string();
+        self
+    }
+
+    /// Number of hot tier slots to allocate up front (default 0)
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Maximum documents in hot tier before eviction (default 1000)
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Lock the data directory so no other exclusive database opens it
+    /// (default false)
+    ///
+    /// `build` fails with `Locked` while another holds it; the lock is
+    /// released when the database is dropped.
+    pub fn exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// Open the database with the configured settings
+    pub fn build(self) -> Result<BurrowDB> {
+        let cold_storage = Storage::new(&self.data_dir)?;
+        let lock = if self.exclusive {
+            Some(DirLock::acquire(Path::new(&self.data_dir))?)
+        } else {
+            None
+        };
+
+        Ok(BurrowDB {
+            hot_data: HashMap::with_capacity(self.capacity),
+            cold_storage,
+            max_hot_blocks: self.max_entries,
+            links: LinkIndex::default(),
+            tags: TagIndex::default(),
+            case_insensitive_keys: false,
+            access_counts: HashMap::new(),
+            max_value_size: None,
+            max_key_size: None,
+            sets: HashMap::new(),
+            log_level: LogLevel::Off,
+            expires_at: HashMap::new(),
+            ttl_lengths: HashMap::new(),
+            transforms: builtin_transforms(),
+            observers: Vec::new(),
+            evict_hook: None,
+            insertion_order: None,
+            compression_threshold: None,
+            overwrite_protection: false,
+            validator: None,
+            lock,
+            deferred_index_drops: None,
+            started_at: Instant::now(),
+            audit: None,
+        })
+    }
+}
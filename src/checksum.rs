@@ -0,0 +1,34 @@
+/// 64-bit FNV-1a hash, used to detect corrupted save files
+///
+/// This is an integrity check against accidental damage, not a
+/// cryptographic hash.
+pub(crate) fn fnv1a64(bytes: &[u8]) -> u64 {
+    fnv1a64_extend(FNV1A64_START, bytes)
+}
+
+/// The FNV-1a hash of no bytes, to start an incremental hash from
+pub(crate) const FNV1A64_START: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Continue an FNV-1a hash over more bytes
+///
+/// Hashing a file piece by piece this way gives the same result as
+/// `fnv1a64` over all of it.
+pub(crate) fn fnv1a64_extend(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a64_known_values() {
+        assert_eq!(fnv1a64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a64(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a64_extend(fnv1a64(b"ab"), b"cd"), fnv1a64(b"abcd"));
+    }
+}
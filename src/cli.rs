@@ -1,13 +1,263 @@
-use crate::BurrowDB;
-use std::io::{self, Write};
+use crate::aggregate::value_type;
+use crate::dot_format::DotFormat;
+use crate::roundtrip::ROUNDTRIP_FORMATS;
+use crate::{BurrowDB, BurrowError, Diff, Event, MergeStrategy, Reducer, Result, VALUE_TYPES};
+use crate::text_format::write_atomic;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// `println!`, but through `styled` so PLAIN mode reaches every line, and
+/// through `write_stdout` so a closed stdout doesn't panic
+macro_rules! say {
+    () => {
+        write_stdout("\n")
+    };
+    ($($arg:tt)*) => {
+        write_stdout(&format!("{}\n", styled(&format!($($arg)*))))
+    };
+}
+
+/// `say!` for a command that failed, so a STRICT session can stop
+macro_rules! fail {
+    ($($arg:tt)*) => {{
+        COMMAND_FAILED.store(true, Ordering::Relaxed);
+        say!($($arg)*)
+    }};
+}
+
+/// Set by `fail!`, and cleared before each command the run loop starts
+///
+/// Global so failures reported from nested commands (TIME, aliases) and
+/// free functions count too.
+static COMMAND_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Whether output is plain ASCII, toggled by `set_plain`
+///
+/// Process-wide rather than per-CLI, since WATCH reports are printed by
+/// database observers that have no CLI to ask.
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// The first failed write to stdout, left for `run` to pick up
+///
+/// Kept globally for the same reason as `PLAIN`.
+static OUTPUT_ERROR: Mutex<Option<io::Error>> = Mutex::new(None);
+
+/// Symbols PLAIN mode rewrites, with their ASCII stand-ins
+///
+/// Status markers become tags; decorative ones have an empty stand-in and
+/// are dropped with the spacing after them.
+const PLAIN_SYMBOLS: &[(&str, &str)] = &[
+    ("✓", "[ok]"),
+    ("❌", "[error]"),
+    ("⚠", "[warn]"),
+    ("❓", "[?]"),
+    ("→", "->"),
+    ("…", "..."),
+    ("µ", "u"),
+    ("±", "+/-"),
+    ("↩", ""), ("↪", ""), ("⏱", ""), ("⏳", ""), ("⌛", ""),
+    ("⚙", ""), ("♾", ""), ("🦀", ""), ("👋", ""), ("📋", ""),
+    ("🔎", ""), ("🔍", ""), ("🔬", ""), ("📣", ""), ("👀", ""),
+    ("🔢", ""), ("🏷", ""), ("🕒", ""), ("📥", ""), ("📝", ""),
+    ("📄", ""), ("📊", ""), ("🔥", ""), ("🔗", ""), ("🧹", ""),
+    ("🧬", ""), ("💚", ""), ("🐢", ""), ("🎲", ""), ("🌳", ""),
+    ("📂", ""), ("🔓", ""), ("📜", ""), ("ℹ", ""),
+];
+
+/// Command names offered by COMPLETE
+const COMMANDS: &[&str] = &[
+    "AGG", "ALIAS", "ALIASES", "AUDIT", "BEGIN", "CHANGEDSINCE", "COMMIT", "COMPLETE", "CONFIG",
+    "COPY", "DEBUG", "DELETE", "DELMATCH", "DEMOTE", "DIFF", "DIFFFILE", "DRYRUN", "EDIT", "EXISTS",
+    "EXIT", "EXPIRE", "EXPIREMATCH", "EXPORT", "EXPORTDOT", "FINDVAL", "FIRST", "FLUSH", "FOLD",
+    "GET", "GETSET", "GROUPS", "HEALTH", "HELP", "HOTKEYS", "INFO", "LAST", "LASTERR", "LINK",
+    "LINKEDTO", "LIST", "LOAD", "LOGLEVEL", "MAXDISPLAY", "MERGE", "MGET", "NRANGE", "PATCH",
+    "PERSIST", "PING", "PLAIN", "PROMOTE", "PUT", "QUIT", "RATELIMIT", "RAWPUT", "REAP", "REDO",
+    "RENPREFIX", "REPLACE", "RESERVE", "ROLLBACK", "SADD", "SAMPLE", "SCAN", "SETTINGS", "SMEMBERS",
+    "SORTBY", "SREM", "STATS", "STRICT", "SUBSCRIBE", "TAG", "TAGGED", "TAGS", "TAIL", "TIME",
+    "TOUCH", "TREE", "TTL", "TYPES", "UNALIAS", "UNDO", "UNSUBSCRIBE", "UNTAGGED", "UNWATCH",
+    "UPTIME", "VERIFY", "VERIFYFILE", "VERSION", "WATCH",
+];
+
+/// Commands whose first argument is an existing key
+const KEY_COMMANDS: &[&str] = &[
+    "COPY", "DEBUG", "DELETE", "DEMOTE", "EDIT", "EXISTS", "EXPIRE", "GET", "GETSET", "LINK",
+    "LINKEDTO", "MGET", "PATCH", "PERSIST", "PROMOTE", "REPLACE", "TAG", "TOUCH", "TTL", "WATCH",
+];
+
+/// Audit log entries AUDIT shows when not given a count
+const AUDIT_SHOWN: usize = 20;
+
+/// LOAD prints a progress line after every this many documents
+const LOAD_PROGRESS_EVERY: usize = 10_000;
+
+/// Number of mutations UNDO can step back through
+const UNDO_DEPTH: usize = 100;
+
+/// Default hot tier fill percentage that triggers a warning after PUT
+pub const DEFAULT_WARN_THRESHOLD: f64 = 90.0;
+
+/// A PUT, REPLACE, GETSET, PATCH, COPY, EDIT or DELETE, recorded with the value before and after it
+///
+/// `None` means the key did not exist.
+struct Edit {
+    key: String,
+    before: Option<Vec<u8>>,
+    after: Option<Vec<u8>>,
+}
+
+/// Token bucket holding at most one token, so commands are evenly spaced
+struct RateLimiter {
+    ops_per_sec: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    fn new(ops_per_sec: f64) -> Self {
+        Self { ops_per_sec, tokens: 1.0, last_refill: Instant::now() }
+    }
+
+    /// Sleep until a token is available, then spend it
+    fn acquire(&mut self) {
+        let now = Instant::now();
+        let refill = now.duration_since(self.last_refill).as_secs_f64() * self.ops_per_sec;
+        self.tokens = (self.tokens + refill).min(1.0);
+        self.last_refill = now;
+
+        if self.tokens < 1.0 {
+            std::thread::sleep(Duration::from_secs_f64((1.0 - self.tokens) / self.ops_per_sec));
+            self.last_refill = Instant::now();
+            self.tokens = 1.0;
+        }
+        self.tokens -= 1.0;
+    }
+}
 
 pub struct CLI<'a> {
     db: &'a mut BurrowDB,
+    /// Mutations that UNDO can revert, oldest first
+    undo: VecDeque<Edit>,
+    /// Reverted mutations that REDO can reapply, most recent last
+    redo: Vec<Edit>,
+    /// Preview destructive commands instead of running them
+    dry_run: bool,
+    /// PUTs (`Some`) and DELETEs (`None`) queued since BEGIN
+    staged: Option<Vec<(String, Option<Vec<u8>>)>>,
+    /// Hot tier fill percentage above which PUT prints a warning
+    warn_threshold: f64,
+    /// Keys reported by WATCH, shared with the observer it subscribes
+    watched: Option<Arc<Mutex<HashSet<String>>>>,
+    /// ALIAS name → command template
+    aliases: BTreeMap<String, String>,
+    /// Where aliases are saved whenever they change, if anywhere
+    aliases_file: Option<PathBuf>,
+    /// Set by RATELIMIT to pace commands
+    rate_limit: Option<RateLimiter>,
+    /// SUBSCRIBE patterns and the channels their events arrive on
+    subscriptions: Vec<(String, Receiver<Event>)>,
+    /// The last terminal I/O failure, shown by LASTERR
+    last_error: Option<String>,
+    /// Set by MAXDISPLAY: longest value shown in full, in characters
+    max_display: Option<usize>,
+    /// Set by STRICT: stop at the first command that fails
+    strict: bool,
+    /// Whether STRICT stopped the session
+    aborted: bool,
+    /// Whether stdin is a terminal, so EDIT and DELMATCH may prompt on it
+    interactive: bool,
 }
 
 impl<'a> CLI<'a> {
     pub fn new(db: &'a mut BurrowDB) -> Self {
-        Self { db }
+        Self {
+            db,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            dry_run: false,
+            staged: None,
+            warn_threshold: DEFAULT_WARN_THRESHOLD,
+            watched: None,
+            aliases: BTreeMap::new(),
+            aliases_file: None,
+            rate_limit: None,
+            subscriptions: Vec::new(),
+            last_error: None,
+            max_display: None,
+            strict: false,
+            aborted: false,
+            interactive: io::stdin().is_terminal(),
+        }
+    }
+
+    /// Load aliases from `path` and save them back there when they change
+    ///
+    /// The file holds one `name<TAB>template` line per alias. A missing file
+    /// starts with no aliases; it is created by the first ALIAS.
+    pub fn aliases_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                for line in contents.lines() {
+                    let Some((name, template)) = line.split_once('\t') else {
+                        continue;
+                    };
+                    match check_template(template) {
+                        Ok(()) => {
+                            self.aliases.insert(name.to_string(), template.to_string());
+                        }
+                        Err(msg) => say!("⚠️  Skipping alias {}: {}", name, msg),
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => say!("⚠️  Could not read aliases from {}: {}", path.display(), e),
+        }
+        self.aliases_file = Some(path);
+        self
+    }
+
+    /// Warn when a PUT fills the hot tier past `percent`
+    pub fn warn_threshold(mut self, percent: f64) -> Self {
+        self.warn_threshold = percent;
+        self
+    }
+
+    /// Start with dry-run mode on or off (it can be toggled with DRYRUN)
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Whether EDIT and DELMATCH may read answers from stdin
+    ///
+    /// Defaults to whether stdin is a terminal. In a piped script the lines
+    /// after those commands are more commands, not answers, so EDIT is
+    /// refused and DELMATCH needs its answer inline as `DELMATCH <pattern> YES`.
+    pub fn interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Whether STRICT mode ended `run` early because a command failed
+    ///
+    /// Scripts piped into the shell use this to exit non-zero.
+    pub fn aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// The last terminal read or write that failed, if any
+    ///
+    /// Failures are recorded rather than panicking; a failed write to
+    /// stdout also ends `run`, since nothing more can be shown.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
     }
 
     pub fn run(&mut self) {
@@ -15,184 +265,2501 @@ impl<'a> CLI<'a> {
         
         loop {
             self.print_prompt();
+            if self.output_failed() {
+                // Stdout is gone (say `burrow_db | head` has exited), so
+                // stop quietly instead of reading commands nobody sees
+                break;
+            }
             
             let input = match self.read_input() {
-                Ok(input) => input,
-                Err(_) => {
-                    println!("❌ Failed to read input");
+                Ok(Some(input)) => input,
+                Ok(None) => {
+                    // End of input (Ctrl-D or the end of a piped file) acts like EXIT
+                    say!();
+                    self.handle_exit();
+                    break;
+                }
+                Err(e) => {
+                    fail!("❌ Failed to read input: {}", e);
+                    self.last_error = Some(format!("reading input: {}", e));
                     continue;
                 }
             };
             
-            if input.is_empty() {
+            if input.trim().is_empty() {
                 continue;
             }
+
+            // Meta-commands never touch data, so they are never held back
+            let meta = input.trim_start().starts_with('.');
+            if let (Some(limiter), false) = (&mut self.rate_limit, meta) {
+                limiter.acquire();
+            }
             
+            COMMAND_FAILED.store(false, Ordering::Relaxed);
             if self.handle_command(&input) {
                 break; // Exit requested
             }
+            if self.strict && COMMAND_FAILED.load(Ordering::Relaxed) {
+                say!("❌ Stopping: STRICT is on and the last command failed");
+                self.aborted = true;
+                break;
+            }
+            self.report_subscriptions();
         }
     }
 
     fn print_welcome(&self) {
-        println!("🦀 BurrowDB CLI - Block-Based Document Database");
-        println!("Commands: PUT <key> <json> | GET <key> | DELETE <key> | LIST | STATS | FLUSH | HELP | EXIT");
-        println!("Example: PUT user:1 {{\"name\": \"Alice\", \"age\": 30}}");
-        println!();
+        say!("🦀 BurrowDB CLI - Block-Based Document Database");
+        say!("Commands: PUT <key> <value> | GET <key> | DELETE <key> | LIST | STATS | FLUSH | HELP | EXIT");
+        say!("Meta-commands start with '.' (.help, .stats, .exit) and never touch data");
+        say!("Example: PUT user:1 {{\"name\": \"Alice\", \"age\": 30}}");
+        say!();
     }
 
     fn print_prompt(&self) {
-        print!("burrow> ");
-        io::stdout().flush().unwrap();
+        write_stdout("burrow> ");
+    }
+
+    /// Record a failed write to stdout, returning whether there was one
+    fn output_failed(&mut self) -> bool {
+        let failed = OUTPUT_ERROR.lock().unwrap_or_else(|e| e.into_inner()).take();
+        match failed {
+            Some(e) => {
+                self.last_error = Some(format!("writing output: {}", e));
+                true
+            }
+            None => false,
+        }
     }
 
-    fn read_input(&self) -> io::Result<String> {
+    /// Read one line without its line ending, or `None` once stdin is closed
+    ///
+    /// Other spacing is kept for RAWPUT.
+    fn read_input(&self) -> io::Result<Option<String>> {
         let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        Ok(input.trim().to_string())
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(input.trim_end_matches(['\n', '\r']).to_string()))
     }
 
-    fn handle_command(&mut self, input: &str) -> bool {
+    fn handle_command(&mut self, line: &str) -> bool {
+        // Split off before trimming, which would lose the value's spacing
+        if let Some((key, value)) = split_raw_put(line) {
+            self.handle_put(key, value, false);
+            return false;
+        }
+
+        let input = line.trim();
+        if let Some(meta) = input.strip_prefix('.') {
+            return self.handle_meta_command(meta);
+        }
+
         let parts: Vec<&str> = input.split_whitespace().collect();
 
         match parts.as_slice() {
+            ["RATELIMIT", rate] => match rate.parse::<f64>() {
+                Ok(rate) if rate >= 0.0 && rate.is_finite() => self.set_rate_limit(rate),
+                _ => fail!("❌ RATELIMIT expects a non-negative number of commands per second"),
+            },
+            ["TIME", _, ..] => {
+                // Run the rest of the line exactly as if it had been typed alone
+                let command = line.trim_start()["TIME".len()..].trim_start();
+                return self.handle_time(command);
+            }
+            [name, args @ ..] if self.aliases.contains_key(*name) => {
+                // Templates run a built-in command, possibly under TIME, so
+                // this never expands a second time (see `check_template`)
+                match expand_alias(&self.aliases[*name], args) {
+                    Ok(command) => return self.handle_command(&command),
+                    Err(msg) => fail!("❌ {}: {}", name, msg),
+                }
+            }
+            ["ALIAS", name, template @ ..] if !template.is_empty() => {
+                self.handle_alias(name, &template.join(" "));
+            }
+            ["ALIASES"] => {
+                self.handle_aliases();
+            }
+            ["UNALIAS", name] => {
+                self.handle_unalias(name);
+            }
+            ["RAWPUT", ..] => {
+                fail!("❌ RAWPUT expects a key, one space, then the value");
+            }
             ["PUT", key, value] => {
-                self.handle_put(key, value);
+                self.handle_put(key, value, false);
+            }
+            ["PUT", key, values @ .., "EXAT", epoch] if !values.is_empty() => match epoch.parse() {
+                Ok(epoch) => self.handle_put_exat(key, &values.join(" "), epoch),
+                Err(_) => fail!("❌ EXAT expects a Unix time in seconds"),
+            },
+            // Without overwrite protection there is nothing to force, so a
+            // trailing FORCE is just the last word of the value
+            ["PUT", key, values @ .., "FORCE"]
+                if !values.is_empty() && self.db.config().overwrite_protection =>
+            {
+                let value = values.join(" ");
+                self.handle_put(key, &value, true);
             }
             ["PUT", key, values @ ..] => {
                 let value = values.join(" ");
-                self.handle_put(key, &value);
+                self.handle_put(key, &value, false);
+            }
+            ["REPLACE", key, values @ ..] if !values.is_empty() => {
+                let value = values.join(" ");
+                self.handle_replace(key, &value);
+            }
+            ["GETSET", key, values @ ..] if !values.is_empty() => {
+                let value = values.join(" ");
+                self.handle_getset(key, &value);
+            }
+            ["PATCH", key, json @ ..] if !json.is_empty() => {
+                self.handle_patch(key, &json.join(" "));
             }
             ["GET", key] => {
                 self.handle_get(key);
             }
+            ["GET", key, "AS", transform] => {
+                self.handle_get_as(key, transform);
+            }
+            ["MGET", keys @ ..] if !keys.is_empty() => {
+                self.handle_mget(keys);
+            }
+            ["SADD", key, member] => {
+                self.handle_sadd(key, member);
+            }
+            ["SMEMBERS", key] => {
+                self.handle_smembers(key);
+            }
+            ["SREM", key, member] => {
+                self.handle_srem(key, member);
+            }
+            ["COPY", from, to] => {
+                self.handle_copy(from, to, false);
+            }
+            ["COPY", from, to, "FORCE"] => {
+                self.handle_copy(from, to, true);
+            }
+            ["RENPREFIX", old, new] => {
+                self.handle_renprefix(old, new);
+            }
+            ["DELMATCH", pattern] => {
+                self.handle_delmatch(pattern, false);
+            }
+            ["DELMATCH", pattern, "YES"] => {
+                self.handle_delmatch(pattern, true);
+            }
+            ["EDIT", key] => {
+                self.handle_edit(key);
+            }
             ["DELETE", key] => {
                 self.handle_delete(key);
             }
-            ["LIST"] => {
+            ["FIRST"] => {
+                self.handle_edge_key("FIRST", BurrowDB::first_key);
+            }
+            ["LAST"] => {
+                self.handle_edge_key("LAST", BurrowDB::last_key);
+            }
+            ["SAMPLE", n] => match n.parse() {
+                Ok(n) => self.handle_sample(n, time_seed()),
+                Err(_) => fail!("❌ SAMPLE expects a number"),
+            },
+            ["SAMPLE", n, "SEED", seed] => match (n.parse(), seed.parse()) {
+                (Ok(n), Ok(seed)) => self.handle_sample(n, seed),
+                _ => fail!("❌ SAMPLE expects numbers for the count and SEED"),
+            },
+            ["FINDVAL", values @ ..] if !values.is_empty() => {
+                let value = values.join(" ");
+                self.handle_findval(&value);
+            }
+            ["LIST"] | ["LIST", "FORMAT", "plain"] => {
                 self.handle_list();
             }
+            ["LIST", "FORMAT", format] => {
+                self.handle_list_format(format);
+            }
+            ["SCAN", "CURSOR", cursor, "COUNT", count] => match (cursor.parse(), count.parse()) {
+                (Ok(cursor), Ok(count)) => self.handle_scan(cursor, count),
+                _ => fail!("❌ SCAN expects numbers for CURSOR and COUNT"),
+            },
+            ["TAIL"] => {
+                self.handle_tail(10);
+            }
+            ["TAIL", n] => match n.parse() {
+                Ok(n) => self.handle_tail(n),
+                Err(_) => fail!("❌ TAIL expects a number"),
+            },
+            ["CHANGEDSINCE", secs] => match secs.parse() {
+                Ok(secs) => self.handle_changed_since(secs),
+                Err(_) => fail!("❌ CHANGEDSINCE expects a Unix time in seconds"),
+            },
+            ["SORTBY", "VALUE"] => {
+                self.handle_sort_by_value(false);
+            }
+            ["SORTBY", "VALUE", "DESC"] => {
+                self.handle_sort_by_value(true);
+            }
+            ["TYPES"] => {
+                self.handle_types();
+            }
+            ["AGG"] => {
+                self.handle_agg();
+            }
+            ["NRANGE", min, max] => match (min.parse(), max.parse()) {
+                (Ok(min), Ok(max)) => self.handle_nrange(min, max),
+                _ => fail!("❌ NRANGE expects two integers"),
+            },
+            ["TREE"] => {
+                self.handle_tree("/");
+            }
+            ["TREE", separator] => {
+                self.handle_tree(separator);
+            }
+            ["GROUPS", separator] => {
+                self.handle_groups(separator);
+            }
+            ["HOTKEYS"] => {
+                self.handle_hotkeys(10);
+            }
+            ["HOTKEYS", n] => match n.parse() {
+                Ok(n) => self.handle_hotkeys(n),
+                Err(_) => fail!("❌ HOTKEYS expects a number"),
+            },
+            ["DIFF", data_dir] => {
+                self.handle_diff(data_dir);
+            }
+            ["DIFFFILE", path] => {
+                self.handle_diff_file(path);
+            }
+            ["MERGE", data_dir, strategy] => match strategy.parse() {
+                Ok(strategy) => self.handle_merge(data_dir, strategy),
+                Err(msg) => fail!("❌ {}", msg),
+            },
+            ["BEGIN"] => {
+                self.handle_begin();
+            }
+            ["COMMIT"] => {
+                self.handle_commit();
+            }
+            ["ROLLBACK"] => {
+                self.handle_rollback();
+            }
+            ["UNDO"] => {
+                self.handle_undo();
+            }
+            ["REDO"] => {
+                self.handle_redo();
+            }
             ["STATS"] => {
                 self.handle_stats();
             }
+            ["CONFIG"] => {
+                self.handle_config();
+            }
+            ["INFO"] => {
+                self.handle_info();
+            }
+            ["UPTIME"] => {
+                self.handle_uptime();
+            }
+            ["HEALTH"] => {
+                self.handle_health();
+            }
+            ["VERIFY"] => {
+                self.handle_verify();
+            }
+            ["VERIFYFILE", path] => {
+                self.handle_verify_file(path);
+            }
+            ["LASTERR"] => match &self.last_error {
+                Some(e) => say!("⚠️  Last error: {}", e),
+                None => say!("✓ No errors"),
+            },
+            ["MAXDISPLAY", limit] => match limit.parse::<usize>() {
+                Ok(limit) => self.set_max_display(limit),
+                Err(_) => fail!("❌ MAXDISPLAY expects a number of characters (0 for no limit)"),
+            },
+            ["STRICT", "ON"] => {
+                self.strict = true;
+                say!("✓ Strict on: the first failed command ends the session");
+            }
+            ["STRICT", "OFF"] => {
+                self.strict = false;
+                say!("✓ Strict off");
+            }
+            ["PLAIN", "ON"] => {
+                self.set_plain(true);
+            }
+            ["PLAIN", "OFF"] => {
+                self.set_plain(false);
+            }
+            ["DRYRUN", "ON"] => {
+                self.set_dry_run(true);
+            }
+            ["DRYRUN", "OFF"] => {
+                self.set_dry_run(false);
+            }
+            ["LOGLEVEL", level] => {
+                self.handle_loglevel(level);
+            }
+            ["AUDIT", "ON", size, reads @ ..] if matches!(reads, [] | ["READS"]) => {
+                match size.parse() {
+                    Ok(size) if size > 0 => {
+                        self.db.set_audit_log(size, !reads.is_empty());
+                        let what = if reads.is_empty() { "writes" } else { "writes and reads" };
+                        say!("✓ Auditing {}, keeping the last {} operations", what, size);
+                    }
+                    _ => fail!("❌ AUDIT ON expects a positive number of entries to keep"),
+                }
+            }
+            ["AUDIT", "OFF"] => {
+                self.db.set_audit_log(0, false);
+                say!("✓ Audit log off and cleared");
+            }
+            ["AUDIT"] => {
+                self.handle_audit(AUDIT_SHOWN);
+            }
+            ["AUDIT", n] => match n.parse() {
+                Ok(n) => self.handle_audit(n),
+                Err(_) => fail!("❌ AUDIT expects a number of entries, ON <size> [READS] or OFF"),
+            },
             ["FLUSH"] => {
                 self.handle_flush();
             }
+            ["EXPIRE", key, seconds] => match seconds.parse() {
+                Ok(seconds) => self.handle_expire(key, seconds),
+                Err(_) => fail!("❌ EXPIRE expects a number of seconds"),
+            },
+            ["EXPIREMATCH", pattern, seconds] => match seconds.parse() {
+                Ok(seconds) => self.handle_expire_matching(pattern, seconds, 0, 0),
+                Err(_) => fail!("❌ EXPIREMATCH expects a number of seconds"),
+            },
+            ["EXPIREMATCH", pattern, seconds, "JITTER", jitter] => {
+                match (seconds.parse(), jitter.parse()) {
+                    (Ok(seconds), Ok(jitter)) => {
+                        self.handle_expire_matching(pattern, seconds, jitter, time_seed())
+                    }
+                    _ => fail!("❌ EXPIREMATCH expects numbers of seconds for the TTL and JITTER"),
+                }
+            }
+            ["EXPIREMATCH", pattern, seconds, "JITTER", jitter, "SEED", seed] => {
+                match (seconds.parse(), jitter.parse(), seed.parse()) {
+                    (Ok(seconds), Ok(jitter), Ok(seed)) => {
+                        self.handle_expire_matching(pattern, seconds, jitter, seed)
+                    }
+                    _ => fail!("❌ EXPIREMATCH expects numbers for the TTL, JITTER and SEED"),
+                }
+            }
+            ["DEBUG", key] => {
+                self.handle_debug(key);
+            }
+            ["EXISTS", key] => {
+                if self.db.contains_key(key) {
+                    say!("✓ {} exists", key);
+                } else {
+                    fail!("❌ Key '{}' not found", key);
+                }
+            }
+            ["TTL", key] => {
+                self.handle_ttl(key);
+            }
+            ["PERSIST", key] => {
+                self.handle_persist(key);
+            }
+            ["REAP"] => {
+                self.handle_reap();
+            }
+            ["TOUCH", key] => {
+                self.handle_touch(key);
+            }
+            ["LINK", from, rel, to] => {
+                self.handle_link(from, rel, to);
+            }
+            ["LINKEDTO", target] => {
+                self.handle_linked_to(target);
+            }
+            ["SUBSCRIBE", pattern] => {
+                self.handle_subscribe(pattern);
+            }
+            ["UNSUBSCRIBE", pattern] => {
+                self.handle_unsubscribe(pattern);
+            }
+            ["WATCH", key] => {
+                self.handle_watch(key);
+            }
+            ["UNWATCH", key] => {
+                self.handle_unwatch(key);
+            }
+            ["TAG", key, tag] => {
+                self.handle_tag(key, tag);
+            }
+            ["TAGGED", tag] => {
+                self.handle_tagged(tag);
+            }
+            ["SETTINGS", prefix] => {
+                self.handle_settings(prefix);
+            }
+            ["UNTAGGED"] => {
+                self.handle_untagged();
+            }
+            ["TAGS"] => {
+                self.handle_tags();
+            }
+            ["EXPORTDOT", path] => {
+                self.handle_export_dot(path, false);
+            }
+            ["EXPORTDOT", path, "ALL"] => {
+                self.handle_export_dot(path, true);
+            }
+            ["RESERVE", n] => match n.parse() {
+                Ok(n) => {
+                    let reserved = self.db.reserve(n);
+                    say!("✓ Reserved hot tier room for {} more documents", reserved);
+                }
+                Err(_) => fail!("❌ RESERVE expects a number of documents"),
+            },
+            ["LOAD", path] => {
+                self.handle_load(path);
+            }
+            ["FOLD", reducer] => {
+                self.handle_fold(reducer, |_, _| true);
+            }
+            ["FOLD", reducer, "PREFIX", prefix] => {
+                self.handle_fold(reducer, |key, _| key.starts_with(prefix));
+            }
+            ["FOLD", reducer, "TAG", tag] => {
+                let db = &*self.db;
+                self.handle_fold(reducer, |key, _| db.has_tag(key, tag));
+            }
+            ["EXPORT", path] => {
+                self.handle_export(path, "all documents", |_, _| true);
+            }
+            ["EXPORT", path, "PREFIX", prefix] => {
+                let what = format!("keys starting with '{}'", prefix);
+                self.handle_export(path, &what, |key, _| key.starts_with(prefix));
+            }
+            ["EXPORT", path, "TAG", tag] => {
+                let what = format!("keys tagged '{}'", tag);
+                let db = &*self.db;
+                self.handle_export(path, &what, |key, _| db.has_tag(key, tag));
+            }
             ["PROMOTE", key] => {
                 self.handle_promote(key);
             }
             ["DEMOTE", key] => {
                 self.handle_demote(key);
             }
+            ["COMPLETE"] => {
+                self.handle_complete_command("");
+            }
+            ["COMPLETE", partial] => {
+                self.handle_complete_command(partial);
+            }
+            ["COMPLETE", command, partial] => {
+                self.handle_complete_key(command, partial);
+            }
             ["HELP"] => {
                 self.handle_help();
             }
+            ["VERSION"] => {
+                say!("BurrowDB {}", crate::version());
+            }
+            ["PING"] => {
+                say!("PONG");
+            }
             ["EXIT"] | ["QUIT"] => {
                 self.handle_exit();
                 return true; // Signal to exit
             }
             _ => {
-                println!("❓ Unknown command. Type HELP for available commands.");
+                fail!("❓ Unknown command. Type HELP for available commands.");
             }
         }
 
         false // Continue running
     }
 
-    fn handle_put(&mut self, key: &str, value: &str) {
-        // Delegate to database layer
-        match self.db.put(key.to_string(), value.to_string()) {
-            Ok(()) => self.print_put_success(key, value),
-            Err(e) => println!("❌ Error storing document: {}", e),
+    /// Dispatch a `.`-prefixed control command (the `.` already stripped)
+    ///
+    /// These only touch the session, never the data, so they can't be
+    /// confused with data commands whatever keys a script uses.
+    fn handle_meta_command(&mut self, input: &str) -> bool {
+        let parts: Vec<&str> = input.split_whitespace().collect();
+
+        match parts.as_slice() {
+            ["help"] => self.handle_meta_help(),
+            ["exit"] | ["quit"] => {
+                self.handle_exit();
+                return true;
+            }
+            ["stats"] => self.handle_stats(),
+            ["config"] => self.handle_config(),
+            ["health"] => self.handle_health(),
+            ["plain", "on"] => self.set_plain(true),
+            ["plain", "off"] => self.set_plain(false),
+            ["dryrun", "on"] => self.set_dry_run(true),
+            ["dryrun", "off"] => self.set_dry_run(false),
+            ["loglevel", level] => self.handle_loglevel(level),
+            _ => say!("❓ Unknown meta-command. Type .help for available meta-commands."),
         }
+
+        false
     }
 
-    fn handle_get(&mut self, key: &str) {
-        // Delegate to database layer
-        match self.db.get(key) {
-            Ok(Some(json)) => self.print_get_result(key, &json),
-            Ok(None) => println!("❌ Key '{}' not found", key),
-            Err(e) => println!("❌ Error retrieving document: {}", e),
+    fn handle_alias(&mut self, name: &str, template: &str) {
+        if COMMANDS.contains(&name) || name.starts_with('.') {
+            fail!("❌ '{}' is a command and can't be an alias", name);
+            return;
         }
+        if let Err(msg) = check_template(template) {
+            fail!("❌ {}", msg);
+            return;
+        }
+
+        self.aliases.insert(name.to_string(), template.to_string());
+        say!("✓ Alias {} = {}", name, template);
+        self.save_aliases();
     }
 
-    fn handle_delete(&mut self, key: &str) {
-        match self.db.delete(key) {
-            Ok(()) => println!("✓ Deleted: {}", key),
-            Err(e) => println!("❌ Error deleting document: {}", e),
+    fn handle_aliases(&self) {
+        if self.aliases.is_empty() {
+            say!("📋 No aliases defined");
+            return;
+        }
+        say!("📋 Aliases ({} total):", self.aliases.len());
+        for (name, template) in &self.aliases {
+            say!("  {} = {}", name, template);
         }
     }
 
-    fn handle_list(&mut self) {
-        match self.db.keys() {
-            Ok(keys) => {
-                if keys.is_empty() {
-                    println!("📋 No documents in database");
-                } else {
-                    println!("📋 All keys in database ({} total):", keys.len());
-                    for key in keys {
-                        println!("  - {}", key);
-                    }
-                }
-            }
-            Err(e) => println!("❌ Error listing keys: {}", e),
+    fn handle_unalias(&mut self, name: &str) {
+        if self.aliases.remove(name).is_some() {
+            say!("✓ Removed alias {}", name);
+            self.save_aliases();
+        } else {
+            fail!("❌ No alias named '{}'", name);
         }
     }
 
-    fn handle_stats(&self) {
-        let stats = self.db.stats();
-        println!("📊 Database Statistics:");
-        println!("  Hot blocks: {}", stats.hot_blocks);
-        println!("  Total hot size: {} bytes", stats.total_hot_size);
+    fn save_aliases(&self) {
+        let Some(path) = &self.aliases_file else {
+            return;
+        };
+        let contents: String = self
+            .aliases
+            .iter()
+            .map(|(name, template)| format!("{}\t{}\n", name, template))
+            .collect();
+        if let Err(e) = write_atomic(path, contents.as_bytes()) {
+            say!("⚠️  Could not save aliases to {}: {}", path.display(), e);
+        }
     }
 
-    fn handle_flush(&mut self) {
-        match self.db.flush_all() {
-            Ok(()) => println!("✓ Flushed all hot data to disk"),
-            Err(e) => println!("❌ Error flushing data: {}", e),
+    /// Run `command` and report how long it took
+    fn handle_time(&mut self, command: &str) -> bool {
+        let start = Instant::now();
+        let exit = self.handle_command(command);
+        say!("⏱️  {} µs", start.elapsed().as_micros());
+        exit
+    }
+
+    fn set_rate_limit(&mut self, ops_per_sec: f64) {
+        if ops_per_sec == 0.0 {
+            self.rate_limit = None;
+            say!("✓ Rate limit off");
+        } else {
+            self.rate_limit = Some(RateLimiter::new(ops_per_sec));
+            say!("🐢 Commands limited to {} per second", ops_per_sec);
         }
     }
 
-    fn handle_promote(&mut self, key: &str) {
-        match self.db.promote(key) {
-            Ok(()) => println!("✓ Promoted {} to hot tier", key),
-            Err(e) => println!("❌ Error promoting: {}", e),
+    fn set_max_display(&mut self, limit: usize) {
+        if limit == 0 {
+            self.max_display = None;
+            say!("✓ Values shown in full");
+        } else {
+            self.max_display = Some(limit);
+            say!("✓ Values cut short after {} characters", limit);
         }
     }
 
-    fn handle_demote(&mut self, key: &str) {
-        match self.db.demote(key) {
-            Ok(()) => println!("✓ Demoted {} to cold tier", key),
-            Err(e) => println!("❌ Error demoting: {}", e),
+    fn set_plain(&mut self, plain: bool) {
+        set_plain(plain);
+        if plain {
+            say!("✓ Plain output on");
+        } else {
+            say!("✓ Plain output off");
         }
     }
 
-    fn handle_help(&self) {
-        println!("Available commands:");
-        println!("  PUT <key> <json>   - Store a JSON document");
-        println!("  GET <key>          - Retrieve a document");
-        println!("  DELETE <key>       - Delete a document");
-        println!("  LIST               - Show all keys");
-        println!("  STATS              - Show database statistics");
-        println!("  FLUSH              - Flush hot data to disk");
-        println!("  PROMOTE <key>      - Move document to hot tier");
-        println!("  DEMOTE <key>       - Move document to cold tier");
-        println!("  HELP               - Show this help");
-        println!("  EXIT               - Quit the program");
+    fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+        if dry_run {
+            say!("🔎 Dry run on: destructive commands will only be previewed");
+        } else {
+            say!("✓ Dry run off");
+        }
     }
 
-    fn handle_exit(&self) {
-        println!("👋 Goodbye from BurrowDB!");
+    /// Print the last `n` audit log entries, oldest first
+    fn handle_audit(&self, n: usize) {
+        let entries = self.db.audit_log();
+        if entries.is_empty() {
+            say!("📜 Audit log is empty (turn it on with AUDIT ON <size> [READS])");
+            return;
+        }
+
+        let shown = &entries[entries.len().saturating_sub(n)..];
+        say!("📜 Last {} of {} audited operations:", shown.len(), entries.len());
+        for entry in shown {
+            let secs = entry.at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            say!("  {} {:<6} {}", secs, entry.op, entry.key);
+        }
     }
 
-    // Pure presentation methods - no database logic
-    fn print_put_success(&self, key: &str, value: &str) {
-        println!("✓ Stored: {} = {}", key, value);
+    fn handle_loglevel(&mut self, level: &str) {
+        match level.parse() {
+            Ok(level) => {
+                self.db.set_log_level(level);
+                say!("✓ Log level set to {}", level);
+            }
+            Err(msg) => fail!("❌ {}", msg),
+        }
     }
 
-    fn print_get_result(&self, key: &str, json: &str) {
-        println!("📄 {}: {}", key, json);
+    /// Store `value`; `force` overwrites even with overwrite protection on
+    fn handle_put(&mut self, key: &str, value: &str, force: bool) {
+        if !force && self.db.config().overwrite_protection && self.db.contains_key(key) {
+            fail!("❌ Key '{}' exists, use FORCE to overwrite", key);
+            return;
+        }
+
+        if let Some(staged) = &mut self.staged {
+            staged.push((key.to_string(), Some(value.as_bytes().to_vec())));
+            say!("📥 Queued: PUT {} ({} pending)", key, staged.len());
+            return;
+        }
+
+        let before = match self.db.peek(key) {
+            Ok(before) => before,
+            Err(e) => {
+                fail!("❌ Error storing document: {}", e);
+                return;
+            }
+        };
+
+        let load_before = self.db.load_factor();
+
+        // Delegate to database layer; protection was checked above
+        match self.db.put_raw_force(key.to_string(), value.as_bytes().to_vec()) {
+            Ok(()) => {
+                self.record(Edit {
+                    key: key.to_string(),
+                    before,
+                    after: Some(value.as_bytes().to_vec()),
+                });
+                self.print_put_success(key, value);
+                self.warn_if_filling(load_before);
+            }
+            Err(e) => fail!("❌ Error storing document: {}", e),
+        }
     }
-}
 
+    fn handle_put_exat(&mut self, key: &str, value: &str, epoch_secs: u64) {
+        if self.refuse_in_transaction("PUT ... EXAT") {
+            return;
+        }
+
+        let before = match self.db.peek(key) {
+            Ok(before) => before,
+            Err(e) => {
+                fail!("❌ Error storing document: {}", e);
+                return;
+            }
+        };
+
+        match self.db.put_with_expiry_at(key.to_string(), value.as_bytes().to_vec(), epoch_secs) {
+            Ok(stored) => {
+                let after = stored.then(|| value.as_bytes().to_vec());
+                if before.is_some() || after.is_some() {
+                    self.record(Edit { key: key.to_string(), before, after });
+                }
+                if stored {
+                    say!("✓ Stored: {} = {} (expires at {})", key, value, epoch_secs);
+                } else {
+                    say!("⌛ {} has already passed: {} is not stored", epoch_secs, key);
+                }
+            }
+            Err(BurrowError::KeyExists(_)) => {
+                fail!("❌ Key '{}' exists, use FORCE to overwrite", key);
+            }
+            Err(e) => fail!("❌ Error storing document: {}", e),
+        }
+    }
+
+    /// Warn once when the hot tier crosses the warning threshold
+    fn warn_if_filling(&self, load_before: Option<f64>) {
+        let (Some(before), Some(after)) = (load_before, self.db.load_factor()) else {
+            return;
+        };
+
+        let threshold = self.warn_threshold / 100.0;
+        if before <= threshold && after > threshold {
+            say!(
+                "⚠️  Hot tier is {:.1}% full; new documents will soon evict older ones to disk",
+                after * 100.0
+            );
+        }
+    }
+
+    fn handle_replace(&mut self, key: &str, value: &str) {
+        if self.refuse_in_transaction("REPLACE") {
+            return;
+        }
+
+        match self.db.replace(key, value.as_bytes().to_vec()) {
+            Ok(old) => {
+                say!(
+                    "✓ Replaced: {} = {} (was {})",
+                    key,
+                    self.shown(value.as_bytes()),
+                    self.shown(&old)
+                );
+                self.record(Edit {
+                    key: key.to_string(),
+                    before: Some(old),
+                    after: Some(value.as_bytes().to_vec()),
+                });
+            }
+            Err(e) => fail!("❌ Error replacing document: {}", e),
+        }
+    }
+
+    fn handle_getset(&mut self, key: &str, value: &str) {
+        if self.refuse_in_transaction("GETSET") {
+            return;
+        }
+
+        match self.db.getset(key, value.as_bytes().to_vec()) {
+            Ok(old) => {
+                let was = match &old {
+                    Some(old) => self.shown(old),
+                    None => "not set".to_string(),
+                };
+                say!("✓ Set: {} = {} (was {})", key, self.shown(value.as_bytes()), was);
+                self.record(Edit {
+                    key: key.to_string(),
+                    before: old,
+                    after: Some(value.as_bytes().to_vec()),
+                });
+            }
+            Err(e) => fail!("❌ Error storing document: {}", e),
+        }
+    }
+
+    fn handle_patch(&mut self, key: &str, patch: &str) {
+        if self.refuse_in_transaction("PATCH") {
+            return;
+        }
+
+        let before = match self.db.peek(key) {
+            Ok(before) => before,
+            Err(e) => {
+                fail!("❌ Error patching {}: {}", key, e);
+                return;
+            }
+        };
+
+        let result = self.db.merge_json(key, patch).and_then(|()| self.db.peek(key));
+        match result {
+            Ok(after) => {
+                if let Some(value) = &after {
+                    say!("✓ Patched: {} = {}", key, self.shown(value));
+                }
+                self.record(Edit { key: key.to_string(), before, after });
+            }
+            Err(e) => fail!("❌ Error patching {}: {}", key, e),
+        }
+    }
+
+    fn handle_copy(&mut self, from: &str, to: &str, overwrite: bool) {
+        if self.refuse_in_transaction("COPY") {
+            return;
+        }
+
+        let before = match self.db.peek(to) {
+            Ok(before) => before,
+            Err(e) => {
+                fail!("❌ Error reading document: {}", e);
+                return;
+            }
+        };
 
+        match self.db.copy(from, to, overwrite) {
+            Ok(()) => {
+                say!("✓ Copied {} to {}", from, to);
+                if let Ok(after) = self.db.peek(to) {
+                    self.record(Edit { key: to.to_string(), before, after });
+                }
+            }
+            Err(BurrowError::KeyExists(_)) => {
+                fail!("❌ Key '{}' exists, use FORCE to overwrite", to);
+            }
+            Err(e) => fail!("❌ Error copying document: {}", e),
+        }
+    }
+
+    fn handle_edit(&mut self, key: &str) {
+        if self.refuse_in_transaction("EDIT") {
+            return;
+        }
+        if !self.interactive {
+            // The lines that follow are the script's next commands
+            fail!("❌ EDIT needs a terminal to read the value from; use PUT or RAWPUT instead");
+            return;
+        }
+
+        let before = match self.db.peek(key) {
+            Ok(before) => before,
+            Err(e) => {
+                fail!("❌ Error reading document: {}", e);
+                return;
+            }
+        };
+
+        match &before {
+            Some(value) => {
+                say!("📝 Current value of {}:", key);
+                say!("{}", String::from_utf8_lossy(value));
+                say!("📝 Type the new value, then a line with only '.' (no lines keeps it)");
+            }
+            None => say!("📝 New key {}: type the value, then a line with only '.'", key),
+        }
+
+        let mut lines = Vec::new();
+        loop {
+            write_stdout("... ");
+
+            let mut line = String::new();
+            match io::stdin().read_line(&mut line) {
+                Ok(0) => {
+                    say!();
+                    fail!("❌ Input ended, edit cancelled");
+                    return;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    fail!("❌ Failed to read input: {}", e);
+                    self.last_error = Some(format!("reading input: {}", e));
+                    return;
+                }
+            }
+
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line == "." {
+                break;
+            }
+            lines.push(line.to_string());
+        }
+
+        if lines.is_empty() && before.is_some() {
+            say!("✓ {} unchanged", key);
+            return;
+        }
+
+        // Editing shows the current value first, so it needs no FORCE
+        let value = lines.join("\n");
+        match self.db.put_raw_force(key.to_string(), value.as_bytes().to_vec()) {
+            Ok(()) => {
+                say!("✓ Saved {} ({} lines)", key, lines.len());
+                self.record(Edit {
+                    key: key.to_string(),
+                    before,
+                    after: Some(value.into_bytes()),
+                });
+            }
+            Err(e) => fail!("❌ Error storing document: {}", e),
+        }
+    }
+
+    fn handle_get(&mut self, key: &str) {
+        // Delegate to database layer
+        match self.db.get(key) {
+            Ok(Some(bytes)) => self.print_get_result(key, &bytes),
+            Ok(None) => fail!("❌ Key '{}' not found", key),
+            Err(e) => fail!("❌ Error retrieving document: {}", e),
+        }
+    }
+
+    fn handle_get_as(&self, key: &str, transform: &str) {
+        match self.db.get_transformed(key, transform) {
+            Ok(Some(value)) => self.print_get_result(key, value.as_bytes()),
+            Ok(None) => fail!("❌ Key '{}' not found", key),
+            Err(e) => fail!(
+                "❌ Error transforming document: {} (available: {})",
+                e,
+                self.db.transform_names().join(", ")
+            ),
+        }
+    }
+
+    fn handle_mget(&mut self, keys: &[&str]) {
+        match self.db.get_many(keys) {
+            Ok(values) => {
+                let width = keys.iter().map(|key| key.chars().count()).max().unwrap_or(0);
+                for (key, value) in keys.iter().zip(values) {
+                    match value {
+                        Some(bytes) => {
+                            say!("  {:<width$}  {}", key, self.shown(&bytes))
+                        }
+                        None => say!("  {:<width$}  (not found)", key),
+                    }
+                }
+            }
+            Err(e) => fail!("❌ Error retrieving documents: {}", e),
+        }
+    }
+
+    fn handle_sample(&self, n: usize, seed: u64) {
+        match self.db.sample(n, seed) {
+            Ok(sample) if sample.is_empty() => say!("📋 No documents in database"),
+            Ok(sample) => {
+                say!("🎲 {} sampled documents (SEED {} repeats this sample):", sample.len(), seed);
+                let width = sample.iter().map(|(key, _)| key.chars().count()).max().unwrap_or(0);
+                for (key, value) in sample {
+                    say!("  {:<width$}  {}", key, self.shown(&value));
+                }
+            }
+            Err(e) => fail!("❌ Error sampling documents: {}", e),
+        }
+    }
+
+    fn handle_delete(&mut self, key: &str) {
+        if let Some(staged) = &mut self.staged {
+            staged.push((key.to_string(), None));
+            say!("📥 Queued: DELETE {} ({} pending)", key, staged.len());
+            return;
+        }
+
+        if self.dry_run {
+            if self.db.contains_key(key) {
+                say!("🔎 Would delete: {}", key);
+            } else {
+                say!("🔎 Would delete nothing: key '{}' not found", key);
+            }
+            return;
+        }
+
+        let before = match self.db.peek(key) {
+            Ok(before) => before,
+            Err(e) => {
+                fail!("❌ Error deleting document: {}", e);
+                return;
+            }
+        };
+
+        match self.db.delete(key) {
+            Ok(()) => {
+                // Deleting a missing key changes nothing, so there is nothing to undo
+                if before.is_some() {
+                    self.record(Edit { key: key.to_string(), before, after: None });
+                }
+                say!("✓ Deleted: {}", key);
+            }
+            Err(e) => fail!("❌ Error deleting document: {}", e),
+        }
+    }
+
+    /// Delete keys matching `pattern`, asking first unless `confirmed`
+    fn handle_delmatch(&mut self, pattern: &str, confirmed: bool) {
+        if self.refuse_in_transaction("DELMATCH") {
+            return;
+        }
+
+        let keys = match self.db.keys_matching(pattern) {
+            Ok(keys) => keys,
+            Err(e) => {
+                fail!("❌ Error matching keys: {}", e);
+                return;
+            }
+        };
+
+        if keys.is_empty() {
+            say!("🔍 No keys match {}", pattern);
+            return;
+        }
+
+        say!("🔍 Keys matching {} ({} total):", pattern, keys.len());
+        for key in &keys {
+            say!("  - {}", key);
+        }
+
+        if self.dry_run {
+            say!("🔎 Would delete {} keys", keys.len());
+            return;
+        }
+        if !confirmed && !self.interactive {
+            fail!("❌ No terminal to confirm on; use DELMATCH {} YES to delete them", pattern);
+            return;
+        }
+        if !confirmed && !self.confirm(&format!("Delete these {} keys?", keys.len())) {
+            say!("✓ Nothing deleted");
+            return;
+        }
+
+        match self.db.delete_matching(pattern) {
+            Ok(count) => say!("✓ Deleted {} keys", count),
+            Err(e) => fail!("❌ Error deleting documents: {}", e),
+        }
+    }
+
+    /// Fail and return `true` if a transaction is open
+    ///
+    /// Only PUT and DELETE can be queued; anything else that writes would
+    /// otherwise apply at once, ahead of the changes queued before it.
+    fn refuse_in_transaction(&self, command: &str) -> bool {
+        if self.staged.is_none() {
+            return false;
+        }
+        fail!("❌ {} can't be queued in a transaction; COMMIT or ROLLBACK first", command);
+        true
+    }
+
+    fn handle_begin(&mut self) {
+        if self.staged.is_some() {
+            fail!("❌ A transaction is already open; COMMIT or ROLLBACK it first");
+            return;
+        }
+
+        self.staged = Some(Vec::new());
+        say!("📥 Transaction started: PUT and DELETE are queued until COMMIT");
+    }
+
+    fn handle_commit(&mut self) {
+        let Some(staged) = self.staged.take() else {
+            fail!("❌ No transaction is open");
+            return;
+        };
+
+        // Capture prior values first so the whole commit can be undone
+        let mut edits = Vec::with_capacity(staged.len());
+        for (key, after) in &staged {
+            match self.db.peek(key) {
+                Ok(before) => edits.push(Edit { key: key.clone(), before, after: after.clone() }),
+                Err(e) => {
+                    fail!("❌ Error committing transaction: {}", e);
+                    return;
+                }
+            }
+        }
+
+        // PUT checked overwrite protection when it queued the value
+        let mut tx = self.db.begin();
+        for (key, value) in staged {
+            match value {
+                Some(value) => tx.put_force(&key, value),
+                None => tx.delete(&key),
+            }
+        }
+
+        match tx.commit() {
+            Ok(()) => {
+                say!("✓ Committed {} changes", edits.len());
+                // Staged changes to the same key must be undone newest first,
+                // so each later edit's `before` reflects the earlier write
+                let mut latest: HashMap<String, Option<Vec<u8>>> = HashMap::new();
+                for mut edit in edits {
+                    if let Some(value) = latest.get(&edit.key) {
+                        edit.before = value.clone();
+                    }
+                    latest.insert(edit.key.clone(), edit.after.clone());
+                    if edit.before != edit.after {
+                        self.record(edit);
+                    }
+                }
+            }
+            Err(e) => fail!("❌ Transaction failed, nothing was changed: {}", e),
+        }
+    }
+
+    fn handle_rollback(&mut self) {
+        match self.staged.take() {
+            Some(staged) => say!("↩️  Discarded {} queued changes", staged.len()),
+            None => fail!("❌ No transaction is open"),
+        }
+    }
+
+    fn handle_renprefix(&mut self, old: &str, new: &str) {
+        if self.refuse_in_transaction("RENPREFIX") {
+            return;
+        }
+
+        if self.dry_run {
+            match self.db.keys() {
+                Ok(mut keys) => {
+                    keys.retain(|key| key.starts_with(old));
+                    keys.sort_unstable();
+                    say!("🔎 Would rename {} keys:", keys.len());
+                    for key in keys {
+                        say!("  {} → {}{}", key, new, &key[old.len()..]);
+                    }
+                }
+                Err(e) => fail!("❌ Error listing keys: {}", e),
+            }
+            return;
+        }
+
+        match self.db.rename_prefix(old, new) {
+            Ok(count) => say!("✓ Renamed {} keys from {}* to {}*", count, old, new),
+            Err(e) => fail!("❌ Error renaming keys: {}", e),
+        }
+    }
+
+    fn handle_undo(&mut self) {
+        if self.refuse_in_transaction("UNDO") {
+            return;
+        }
+
+        let Some(edit) = self.undo.pop_back() else {
+            say!("↩️  Nothing to undo");
+            return;
+        };
+
+        match self.apply(&edit.key, edit.before.as_deref()) {
+            Ok(()) => {
+                say!("↩️  Undid change to {}", edit.key);
+                self.redo.push(edit);
+            }
+            Err(e) => {
+                fail!("❌ Error undoing change to {}: {}", edit.key, e);
+                self.undo.push_back(edit);
+            }
+        }
+    }
+
+    fn handle_redo(&mut self) {
+        if self.refuse_in_transaction("REDO") {
+            return;
+        }
+
+        let Some(edit) = self.redo.pop() else {
+            say!("↪️  Nothing to redo");
+            return;
+        };
+
+        match self.apply(&edit.key, edit.after.as_deref()) {
+            Ok(()) => {
+                say!("↪️  Redid change to {}", edit.key);
+                self.undo.push_back(edit);
+            }
+            Err(e) => {
+                fail!("❌ Error redoing change to {}: {}", edit.key, e);
+                self.redo.push(edit);
+            }
+        }
+    }
+
+    /// Ask a yes/no question, treating anything but y/yes (or end of input) as no
+    fn confirm(&self, question: &str) -> bool {
+        write_stdout(&format!("{} ", styled(&format!("❓ {} [y/N]", question))));
+
+        match self.read_input() {
+            Ok(Some(answer)) => matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+            _ => false,
+        }
+    }
+
+    /// Remember a new mutation for UNDO, forgetting anything undone before it
+    fn record(&mut self, edit: Edit) {
+        self.redo.clear();
+        self.undo.push_back(edit);
+        if self.undo.len() > UNDO_DEPTH {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Put `key` back to `value`, deleting it if it should not exist
+    fn apply(&mut self, key: &str, value: Option<&[u8]>) -> Result<()> {
+        match value {
+            Some(value) => self.db.put_raw_force(key.to_string(), value.to_vec()),
+            None => self.db.delete(key),
+        }
+    }
+
+    fn handle_sadd(&mut self, key: &str, member: &str) {
+        if self.refuse_in_transaction("SADD") {
+            return;
+        }
+
+        if self.db.sadd(key, member) {
+            say!("✓ Added {} to set {}", member, key);
+        } else {
+            say!("✓ {} is already in set {}", member, key);
+        }
+    }
+
+    fn handle_smembers(&self, key: &str) {
+        let members = self.db.smembers(key);
+        if members.is_empty() {
+            say!("📋 Set {} is empty", key);
+        } else {
+            say!("📋 Members of {} ({} total):", key, members.len());
+            for member in members {
+                say!("  - {}", member);
+            }
+        }
+    }
+
+    fn handle_srem(&mut self, key: &str, member: &str) {
+        if self.refuse_in_transaction("SREM") {
+            return;
+        }
+
+        if self.db.srem(key, member) {
+            say!("✓ Removed {} from set {}", member, key);
+        } else {
+            fail!("❌ {} is not in set {}", member, key);
+        }
+    }
+
+    fn handle_list(&mut self) {
+        match self.db.keys() {
+            Ok(keys) => {
+                if keys.is_empty() {
+                    say!("📋 No documents in database");
+                } else {
+                    say!("📋 All keys in database ({} total):", keys.len());
+                    for key in keys {
+                        say!("  - {}", key);
+                    }
+                }
+            }
+            Err(e) => fail!("❌ Error listing keys: {}", e),
+        }
+    }
+
+    /// Print every document in a machine-readable format, undecorated
+    fn handle_list_format(&self, format: &str) {
+        let result = match format {
+            "json" => self.db.export_json_filtered(|_, _| true).map(|json| say!("{}", json)),
+            "csv" => self.db.write_csv(&mut io::stdout().lock()),
+            _ => {
+                fail!("❌ Unknown format '{}' (expected json, csv or plain)", format);
+                return;
+            }
+        };
+        if let Err(e) = result {
+            fail!("❌ Error listing documents: {}", e);
+        }
+    }
+
+    fn handle_scan(&self, cursor: usize, count: usize) {
+        match self.db.scan_cursor(cursor, count) {
+            Ok((keys, next)) => {
+                say!("📋 Next cursor: {}", next);
+                for key in keys {
+                    say!("  - {}", key);
+                }
+            }
+            Err(e) => fail!("❌ Error scanning keys: {}", e),
+        }
+    }
+
+    /// Show the key found by `find` (FIRST or LAST) along with its value
+    fn handle_edge_key(&mut self, command: &str, find: fn(&BurrowDB) -> Result<Option<String>>) {
+        match find(self.db) {
+            Ok(Some(key)) => self.handle_get(&key),
+            Ok(None) => say!("📋 No documents in database"),
+            Err(e) => fail!("❌ Error running {}: {}", command, e),
+        }
+    }
+
+    fn handle_tail(&self, n: usize) {
+        match self.db.recent(n) {
+            Ok(recent) if recent.is_empty() => say!("📋 No documents in database"),
+            Ok(recent) => {
+                say!("🕒 {} most recently written, newest first:", recent.len());
+                let width = recent.iter().map(|(key, _)| key.chars().count()).max().unwrap_or(0);
+                for (key, value) in recent {
+                    say!("  {:<width$}  {}", key, self.shown(&value));
+                }
+            }
+            Err(e) => fail!("❌ Error listing recent documents: {}", e),
+        }
+    }
+
+    fn handle_changed_since(&self, epoch_secs: u64) {
+        match self.db.keys_modified_since(epoch_secs) {
+            Ok(keys) if keys.is_empty() => say!("🕒 No keys changed since {}", epoch_secs),
+            Ok(keys) => {
+                say!("🕒 Keys changed since {}, newest first ({} total):", epoch_secs, keys.len());
+                for key in keys {
+                    say!("  - {}", key);
+                }
+            }
+            Err(e) => fail!("❌ Error listing changed keys: {}", e),
+        }
+    }
+
+    fn handle_findval(&self, value: &str) {
+        match self.db.keys_with_value(value.as_bytes()) {
+            Ok(keys) => {
+                if keys.is_empty() {
+                    say!("🔍 No keys hold that value");
+                } else {
+                    say!("🔍 Keys holding that value ({} total):", keys.len());
+                    for key in keys {
+                        say!("  - {}", key);
+                    }
+                }
+            }
+            Err(e) => fail!("❌ Error searching values: {}", e),
+        }
+    }
+
+    fn handle_sort_by_value(&self, descending: bool) {
+        match self.db.entries_sorted_by_value(descending) {
+            Ok(entries) if entries.is_empty() => say!("📋 No documents in database"),
+            Ok(entries) => {
+                let order = if descending { "descending" } else { "ascending" };
+                say!("📋 Documents by value, {} (integers first):", order);
+                let width = entries.iter().map(|(key, _)| key.chars().count()).max().unwrap_or(0);
+                for (key, value) in entries {
+                    say!("  {:<width$}  {}", key, self.shown(&value));
+                }
+            }
+            Err(e) => fail!("❌ Error sorting documents: {}", e),
+        }
+    }
+
+    fn handle_settings(&self, prefix: &str) {
+        match self.db.entries_with_prefix(prefix) {
+            Ok(entries) if entries.is_empty() => say!("⚙️  No settings under {}", prefix),
+            Ok(entries) => {
+                say!("⚙️  Settings under {} ({} total):", prefix, entries.len());
+                let width = entries.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+                for (key, value) in entries {
+                    let kind = value_type(&value);
+                    say!("  {:<width$}  {:<7}  {}", key, kind, self.shown(&value));
+                }
+            }
+            Err(e) => fail!("❌ Error reading settings: {}", e),
+        }
+    }
+
+    fn handle_types(&self) {
+        match self.db.type_histogram() {
+            Ok(histogram) if histogram.is_empty() => say!("📋 No documents in database"),
+            Ok(histogram) => {
+                let total: usize = histogram.values().sum();
+                say!("🧬 Value types ({} documents):", total);
+                for name in VALUE_TYPES {
+                    if let Some(&count) = histogram.get(name) {
+                        let percent = count as f64 * 100.0 / total as f64;
+                        say!("  {:<8} {:>6}  ({:.1}%)", name, count, percent);
+                    }
+                }
+            }
+            Err(e) => fail!("❌ Error reading values: {}", e),
+        }
+    }
+
+    fn handle_agg(&self) {
+        let summary = self.db.count_int_values().and_then(|count| {
+            Ok((count, self.db.sum_values()?, self.db.avg_values()?, self.db.min_max_values()?))
+        });
+        match summary {
+            Ok((0, ..)) => say!("🔢 No integer values"),
+            Ok((count, sum, avg, min_max)) => {
+                say!("🔢 Integer values:");
+                say!("  Count: {}", count);
+                say!("  Sum:   {}", sum);
+                if let Some(avg) = avg {
+                    say!("  Avg:   {:.2}", avg);
+                }
+                if let Some((min, max)) = min_max {
+                    say!("  Min:   {}", min);
+                    say!("  Max:   {}", max);
+                }
+            }
+            Err(e) => fail!("❌ Error aggregating values: {}", e),
+        }
+    }
+
+    /// Print the values accepted by `pred` combined by the named reducer
+    fn handle_fold<F: Fn(&str, &[u8]) -> bool>(&self, reducer: &str, pred: F) {
+        let reducer: Reducer = match reducer.parse() {
+            Ok(reducer) => reducer,
+            Err(msg) => {
+                fail!("❌ {}", msg);
+                return;
+            }
+        };
+        match self.db.fold_values_filtered(reducer, pred) {
+            Ok(result) => say!("{}", self.shown(result.as_bytes())),
+            Err(e) => fail!("❌ Error reading values: {}", e),
+        }
+    }
+
+    fn handle_nrange(&self, min: i64, max: i64) {
+        match self.db.find_int_in_range(min, max) {
+            Ok(keys) => {
+                if keys.is_empty() {
+                    say!("🔢 No integer values in [{}, {}]", min, max);
+                } else {
+                    say!("🔢 Keys with integer values in [{}, {}] ({} total):", min, max, keys.len());
+                    for key in keys {
+                        say!("  - {}", key);
+                    }
+                }
+            }
+            Err(e) => fail!("❌ Error searching values: {}", e),
+        }
+    }
+
+    fn handle_tree(&self, separator: &str) {
+        let Some(separator) = single_char(separator) else {
+            fail!("❌ Separator must be a single character");
+            return;
+        };
+
+        match self.db.tree(separator) {
+            Ok(tree) if tree.is_empty() => say!("📋 No documents in database"),
+            Ok(tree) => {
+                say!("🌳 Keys by '{}':", separator);
+                for line in tree.lines() {
+                    say!("  {}", line);
+                }
+            }
+            Err(e) => fail!("❌ Error building tree: {}", e),
+        }
+    }
+
+    fn handle_groups(&self, separator: &str) {
+        let Some(separator) = single_char(separator) else {
+            fail!("❌ Separator must be a single character");
+            return;
+        };
+
+        match self.db.count_by_prefix(separator) {
+            Ok(groups) => {
+                let mut groups: Vec<_> = groups.into_iter().collect();
+                groups.sort_by(|(a_prefix, a_count), (b_prefix, b_count)| {
+                    b_count.cmp(a_count).then_with(|| a_prefix.cmp(b_prefix))
+                });
+
+                say!("📊 Key groups by '{}' ({} total):", separator, groups.len());
+                for (prefix, count) in groups {
+                    say!("  {:<20} {}", prefix, count);
+                }
+            }
+            Err(e) => fail!("❌ Error grouping keys: {}", e),
+        }
+    }
+
+    fn handle_hotkeys(&self, n: usize) {
+        let top = self.db.top_keys(n);
+        if top.is_empty() {
+            say!("🔥 No reads recorded yet");
+        } else {
+            say!("🔥 Most-read keys:");
+            for (key, count) in top {
+                say!("  {:<20} {}", key, count);
+            }
+        }
+    }
+
+    fn handle_diff(&self, data_dir: &str) {
+        // Opening a database creates its directory, so check first to avoid
+        // leaving an empty one behind for a mistyped path
+        if !Path::new(data_dir).is_dir() {
+            fail!("❌ No database directory at {}", data_dir);
+            return;
+        }
+
+        let result = BurrowDB::builder()
+            .data_dir(data_dir)
+            .build()
+            .and_then(|other| self.db.diff(&other));
+
+        match result {
+            Ok(diff) => self.print_diff(&diff, data_dir),
+            Err(e) => fail!("❌ Error comparing databases: {}", e),
+        }
+    }
+
+    fn handle_diff_file(&self, path: &str) {
+        match self.db.diff_file(path) {
+            Ok(diff) => self.print_diff(&diff, path),
+            Err(e) => fail!("❌ Error comparing with {}: {}", path, e),
+        }
+    }
+
+    fn handle_merge(&mut self, data_dir: &str, strategy: MergeStrategy) {
+        if !Path::new(data_dir).is_dir() {
+            fail!("❌ No database directory at {}", data_dir);
+            return;
+        }
+        let other = match BurrowDB::builder().data_dir(data_dir).build() {
+            Ok(other) => other,
+            Err(e) => {
+                fail!("❌ Error opening {}: {}", data_dir, e);
+                return;
+            }
+        };
+
+        if self.dry_run {
+            match self.db.merge_preview(&other, strategy) {
+                Ok(s) => say!(
+                    "🔎 Would merge {} ({}): {} added, {} overwritten, {} skipped",
+                    data_dir, strategy, s.added, s.overwritten, s.skipped
+                ),
+                Err(e) => fail!("❌ Error merging databases: {}", e),
+            }
+            return;
+        }
+
+        match self.db.merge_from(&other, strategy) {
+            Ok(s) => say!(
+                "✓ Merged {} ({}): {} added, {} overwritten, {} skipped",
+                data_dir, strategy, s.added, s.overwritten, s.skipped
+            ),
+            Err(e) => fail!("❌ Error merging databases: {}", e),
+        }
+    }
+
+    fn handle_stats(&self) {
+        let stats = self.db.stats();
+        say!("📊 Database Statistics:");
+        say!("  Hot blocks: {}", stats.hot_blocks);
+        say!("  Total hot size: {} bytes", stats.total_hot_size);
+        if stats.compressed_blocks > 0 {
+            say!(
+                "  Compressed blocks: {} ({} bytes before compression)",
+                stats.compressed_blocks, stats.raw_hot_size
+            );
+        }
+    }
+
+    fn handle_info(&self) {
+        say!("ℹ️  Database Info:");
+        for line in self.db.info().lines() {
+            say!("  {}", line);
+        }
+    }
+
+    fn handle_uptime(&self) {
+        let uptime = self.db.uptime().as_secs();
+        say!(
+            "⏱️  Up {}h {}m {}s ({} seconds)",
+            uptime / 3600,
+            uptime / 60 % 60,
+            uptime % 60,
+            uptime
+        );
+    }
+
+    fn handle_config(&self) {
+        let config = self.db.config();
+        let limit = |bytes: Option<usize>| match bytes {
+            Some(bytes) => format!("{} bytes", bytes),
+            None => "unlimited".to_string(),
+        };
+
+        say!("⚙️  Database Configuration:");
+        say!("  Data directory: {}", config.data_dir.display());
+        say!("  Max hot blocks: {}", config.max_hot_blocks);
+        say!("  Exclusive lock: {}", config.exclusive);
+        match config.compression_threshold {
+            Some(bytes) => say!("  Compression: values over {} bytes", bytes),
+            None => say!("  Compression: off"),
+        }
+        if let Some(load) = self.db.load_factor() {
+            say!("  Hot tier load: {:.1}% (warning above {}%)", load * 100.0, self.warn_threshold);
+        }
+        say!("  Case-insensitive keys: {}", config.case_insensitive_keys);
+        say!("  Overwrite protection: {}", config.overwrite_protection);
+        say!("  Max value size: {}", limit(config.max_value_size));
+        say!("  Max key size: {}", limit(config.max_key_size));
+        say!("  Log level: {}", config.log_level);
+        say!("  Insertion-ordered keys: {}", config.insertion_order);
+    }
+
+    fn handle_verify(&self) {
+        match self.db.roundtrip_mismatches() {
+            Ok(mismatches) if mismatches.is_empty() => say!(
+                "✓ Every format round-trips cleanly ({})",
+                ROUNDTRIP_FORMATS.join(", ")
+            ),
+            Ok(mismatches) => {
+                say!("⚠️  {} keys did not round-trip:", mismatches.len());
+                for (format, key) in mismatches {
+                    say!("  - {}: {}", format, key);
+                }
+            }
+            Err(e) => fail!("❌ Error verifying formats: {}", e),
+        }
+    }
+
+    fn handle_verify_file(&self, path: &str) {
+        match BurrowDB::verify_save_file(path) {
+            Ok(count) => say!("✓ {} loads cleanly ({} keys)", path, count),
+            Err(BurrowError::SerializationError(problems)) => {
+                say!("⚠️  {} would not load:", path);
+                for problem in problems.split("; ") {
+                    say!("  - {}", problem);
+                }
+            }
+            Err(e) => fail!("❌ Error verifying {}: {}", path, e),
+        }
+    }
+
+    fn handle_health(&self) {
+        let report = self.db.health_check();
+        if report.is_healthy() {
+            say!("💚 Database is healthy");
+        } else {
+            say!("⚠️  Found {} problems:", report.problems.len());
+            for problem in &report.problems {
+                say!("  - {}", problem);
+            }
+        }
+    }
+
+    fn handle_flush(&mut self) {
+        if self.dry_run {
+            say!("🔎 Would flush {} hot blocks to disk", self.db.stats().hot_blocks);
+            return;
+        }
+
+        match self.db.flush_all() {
+            Ok(()) => say!("✓ Flushed all hot data to disk"),
+            Err(e) => fail!("❌ Error flushing data: {}", e),
+        }
+    }
+
+    fn handle_expire(&mut self, key: &str, seconds: u64) {
+        if self.refuse_in_transaction("EXPIRE") {
+            return;
+        }
+
+        if self.db.expire(key, Duration::from_secs(seconds)) {
+            say!("⏳ {} expires in {}s", key, seconds);
+        } else {
+            fail!("❌ Key '{}' not found", key);
+        }
+    }
+
+    fn handle_expire_matching(&mut self, pattern: &str, seconds: u64, jitter: u64, seed: u64) {
+        if self.refuse_in_transaction("EXPIREMATCH") {
+            return;
+        }
+
+        let ttl = Duration::from_secs(seconds);
+        match self.db.expire_matching_with_jitter(pattern, ttl, Duration::from_secs(jitter), seed) {
+            Ok(0) => say!("🔍 No keys match {}", pattern),
+            Ok(count) if jitter > 0 => {
+                say!("⏳ {} keys matching {} expire in {}s ± {}s", count, pattern, seconds, jitter)
+            }
+            Ok(count) => say!("⏳ {} keys matching {} expire in {}s", count, pattern, seconds),
+            Err(e) => fail!("❌ Error matching keys: {}", e),
+        }
+    }
+
+    fn handle_debug(&self, key: &str) {
+        let info = match self.db.inspect(key) {
+            Ok(Some(info)) => info,
+            Ok(None) => {
+                fail!("❌ Key '{}' not found", key);
+                return;
+            }
+            Err(e) => {
+                fail!("❌ Error inspecting document: {}", e);
+                return;
+            }
+        };
+
+        say!("🔬 {}", info.key);
+        say!("  Value:      {}", self.shown(&info.value));
+        say!("  Type:       {} ({} bytes)", info.value_type, info.value.len());
+        let tier = if info.hot { "hot" } else { "cold" };
+        let compressed = if info.compressed { ", compressed" } else { "" };
+        say!("  Tier:       {}{}", tier, compressed);
+        say!("  Updated at: {} (unix time)", info.updated_at);
+        say!("  Reads:      {}", info.reads);
+        match (info.expired, info.ttl) {
+            (true, _) => say!("  TTL:        expired, not yet removed"),
+            (false, Some(left)) => say!("  TTL:        {:.1}s left", left.as_secs_f64()),
+            (false, None) => say!("  TTL:        none"),
+        }
+        say!("  Tags:       {}", if info.tags.is_empty() { "none".to_string() } else { info.tags.join(", ") });
+        for (rel, to) in &info.links_out {
+            say!("  Link:       -[{}]-> {}", rel, to);
+        }
+        for (from, rel) in &info.links_in {
+            say!("  Link:       <-[{}]- {}", rel, from);
+        }
+    }
+
+    fn handle_ttl(&self, key: &str) {
+        match self.db.ttl(key) {
+            Some(left) => say!("⏳ {} expires in {:.1}s", key, left.as_secs_f64()),
+            None if self.db.contains_key(key) => say!("♾️  {} has no expiry", key),
+            None => fail!("❌ Key '{}' not found", key),
+        }
+    }
+
+    fn handle_persist(&mut self, key: &str) {
+        if self.refuse_in_transaction("PERSIST") {
+            return;
+        }
+
+        if self.db.persist(key) {
+            say!("✓ {} will no longer expire", key);
+        } else {
+            fail!("❌ {} has no expiry to remove", key);
+        }
+    }
+
+    fn handle_reap(&mut self) {
+        if self.dry_run {
+            let expired = self.db.expired_keys();
+            say!("🔎 Would remove {} expired keys", expired.len());
+            for key in expired {
+                say!("  - {}", key);
+            }
+            return;
+        }
+
+        match self.db.flush_expired() {
+            Ok(count) => say!("🧹 Removed {} expired keys", count),
+            Err(e) => fail!("❌ Error removing expired keys: {}", e),
+        }
+    }
+
+    fn handle_touch(&mut self, key: &str) {
+        if self.refuse_in_transaction("TOUCH") {
+            return;
+        }
+
+        match self.db.touch(key) {
+            Ok(true) => say!("✓ Touched: {}", key),
+            Ok(false) => fail!("❌ Key '{}' not found", key),
+            Err(e) => fail!("❌ Error touching document: {}", e),
+        }
+    }
+
+    fn handle_link(&mut self, from: &str, rel: &str, to: &str) {
+        match self.db.link(from, rel, to) {
+            Ok(()) => say!("✓ Linked: {} -[{}]-> {}", from, rel, to),
+            Err(e) => fail!("❌ Error linking: {}", e),
+        }
+    }
+
+    fn handle_linked_to(&self, target: &str) {
+        let sources = self.db.find_linked_to(target);
+        if sources.is_empty() {
+            say!("🔗 Nothing links to {}", target);
+        } else {
+            say!("🔗 Keys linking to {} ({} total):", target, sources.len());
+            for source in sources {
+                say!("  - {}", source);
+            }
+        }
+    }
+
+    fn handle_watch(&mut self, key: &str) {
+        let watched = match &self.watched {
+            Some(watched) => Arc::clone(watched),
+            None => {
+                // One observer serves every watched key for the life of the database
+                let watched = Arc::new(Mutex::new(HashSet::new()));
+                let keys = Arc::clone(&watched);
+                self.db.subscribe(Box::new(move |event| report_watched(&keys, event)));
+                self.watched = Some(Arc::clone(&watched));
+                watched
+            }
+        };
+
+        let key = self.db.normalize_key(key).into_owned();
+        if watched.lock().unwrap_or_else(|e| e.into_inner()).insert(key.clone()) {
+            say!("👀 Watching {}", key);
+        } else {
+            say!("👀 Already watching {}", key);
+        }
+    }
+
+    fn handle_unwatch(&mut self, key: &str) {
+        let key = self.db.normalize_key(key);
+        let removed = self
+            .watched
+            .as_ref()
+            .is_some_and(|watched| watched.lock().unwrap_or_else(|e| e.into_inner()).remove(&*key));
+        if removed {
+            say!("✓ Stopped watching {}", key);
+        } else {
+            say!("❓ Not watching {}", key);
+        }
+    }
+
+    fn handle_subscribe(&mut self, pattern: &str) {
+        if self.subscriptions.iter().any(|(p, _)| p == pattern) {
+            say!("📣 Already subscribed to {}", pattern);
+            return;
+        }
+        let receiver = self.db.subscribe_pattern(pattern);
+        self.subscriptions.push((pattern.to_string(), receiver));
+        say!("📣 Subscribed to {} (until UNSUBSCRIBE)", pattern);
+    }
+
+    fn handle_unsubscribe(&mut self, pattern: &str) {
+        let before = self.subscriptions.len();
+        // Dropping the receiver is what stops delivery
+        self.subscriptions.retain(|(p, _)| p != pattern);
+        if self.subscriptions.len() < before {
+            say!("✓ Unsubscribed from {}", pattern);
+        } else {
+            say!("❓ Not subscribed to {}", pattern);
+        }
+    }
+
+    /// Print the events each subscription received during the last command
+    fn report_subscriptions(&self) {
+        for (pattern, receiver) in &self.subscriptions {
+            for event in receiver.try_iter() {
+                match event {
+                    Event::Put { key, value } => {
+                        say!("📣 [{}] PUT {} = {}", pattern, key, self.shown(&value));
+                    }
+                    Event::Deleted { key } => say!("📣 [{}] DELETE {}", pattern, key),
+                    Event::Expired { key, .. } => say!("📣 [{}] EXPIRED {}", pattern, key),
+                }
+            }
+        }
+    }
+
+    fn handle_tag(&mut self, key: &str, tag: &str) {
+        match self.db.tag(key, tag) {
+            Ok(true) => say!("🏷️  Tagged {} as {}", key, tag),
+            Ok(false) => say!("🏷️  {} is already tagged {}", key, tag),
+            Err(e) => fail!("❌ Error tagging: {}", e),
+        }
+    }
+
+    fn handle_tagged(&self, tag: &str) {
+        let keys = self.db.keys_with_tag(tag);
+        if keys.is_empty() {
+            say!("🏷️  No keys tagged {}", tag);
+        } else {
+            say!("🏷️  Keys tagged {} ({} total):", tag, keys.len());
+            for key in keys {
+                say!("  - {}", key);
+            }
+        }
+    }
+
+    fn handle_tags(&self) {
+        let tags = self.db.all_tags();
+        if tags.is_empty() {
+            say!("🏷️  No tags in use");
+        } else {
+            say!("🏷️  Tags ({} total):", tags.len());
+            for (tag, count) in tags {
+                say!("  - {} ({} {})", tag, count, if count == 1 { "key" } else { "keys" });
+            }
+        }
+    }
+
+    fn handle_untagged(&self) {
+        match self.db.untagged_keys() {
+            Ok(keys) if keys.is_empty() => say!("🏷️  Every key is tagged"),
+            Ok(keys) => {
+                say!("🏷️  Untagged keys ({} total):", keys.len());
+                for key in keys {
+                    say!("  - {}", key);
+                }
+            }
+            Err(e) => fail!("❌ Error listing keys: {}", e),
+        }
+    }
+
+    /// Write the link graph to `path` in Graphviz DOT format
+    fn handle_export_dot(&self, path: &str, include_isolated: bool) {
+        let result = self
+            .db
+            .export_dot_with(&DotFormat { include_isolated })
+            .and_then(|dot| Ok(fs::write(path, dot)?));
+        match result {
+            Ok(()) => say!("✓ Exported link graph to {} (render with: dot -Tsvg {})", path, path),
+            Err(e) => fail!("❌ Error exporting: {}", e),
+        }
+    }
+
+    fn handle_load(&mut self, path: &str) {
+        if self.refuse_in_transaction("LOAD") {
+            return;
+        }
+
+        let start = Instant::now();
+        let result = self.db.load_from_file_with_progress(path, LOAD_PROGRESS_EVERY, |count| {
+            say!("  … {} loaded ({:.1}s)", count, start.elapsed().as_secs_f64());
+        });
+        match result {
+            // Loaded documents aren't recorded, so UNDO can't step back over them
+            Ok(count) => say!("✓ Loaded {} documents from {} (not undoable)", count, path),
+            Err(e) => fail!("❌ Error loading {}: {}", path, e),
+        }
+    }
+
+    /// Write the documents accepted by `pred` to `path` as a JSON object
+    fn handle_export<F: Fn(&str, &str) -> bool>(&self, path: &str, what: &str, pred: F) {
+        let result = self
+            .db
+            .export_json_filtered(pred)
+            .and_then(|json| Ok(std::fs::write(path, json)?));
+        match result {
+            Ok(()) => say!("✓ Exported {} to {}", what, path),
+            Err(e) => fail!("❌ Error exporting: {}", e),
+        }
+    }
+
+    fn handle_promote(&mut self, key: &str) {
+        match self.db.promote(key) {
+            Ok(()) => say!("✓ Promoted {} to hot tier", key),
+            Err(e) => fail!("❌ Error promoting: {}", e),
+        }
+    }
+
+    fn handle_demote(&mut self, key: &str) {
+        match self.db.demote(key) {
+            Ok(()) => say!("✓ Demoted {} to cold tier", key),
+            Err(e) => fail!("❌ Error demoting: {}", e),
+        }
+    }
+
+    fn handle_complete_command(&self, partial: &str) {
+        let partial = partial.to_uppercase();
+        let candidates: Vec<&str> = COMMANDS
+            .iter()
+            .copied()
+            .filter(|command| command.starts_with(&partial))
+            .collect();
+        self.print_candidates(&candidates);
+    }
+
+    fn handle_complete_key(&self, command: &str, partial: &str) {
+        if !KEY_COMMANDS.contains(&command) {
+            say!("❓ {} does not take a key", command);
+            return;
+        }
+
+        match self.db.keys() {
+            Ok(keys) => {
+                let mut candidates: Vec<&str> = keys
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|key| key.starts_with(partial))
+                    .collect();
+                candidates.sort_unstable();
+                self.print_candidates(&candidates);
+            }
+            Err(e) => fail!("❌ Error listing keys: {}", e),
+        }
+    }
+
+    fn handle_help(&self) {
+        say!("Available commands:");
+        say!("  PUT <key> <value> [FORCE] - Store a value (FORCE overwrites when protected)");
+        say!("                       (FORCE is part of the value unless protection is on)");
+        say!("  PUT <key> <value> EXAT <epoch> - Store a value expiring at a Unix time");
+        say!("  RAWPUT <key> <value> - Like PUT, but the value is the rest of the line verbatim");
+        say!("                       (PUT joins its words with single spaces; use RAWPUT for");
+        say!("                       a value ending in FORCE or EXAT <number>)");
+        say!("  REPLACE <key> <value> - Overwrite an existing key only");
+        say!("  GET <key>          - Retrieve a document");
+        say!("  GETSET <key> <value> - Store a value and show the one it replaced");
+        say!("  PATCH <key> <json> - Merge a JSON object into the stored one (null removes a field)");
+        say!("  DEBUG <key>        - Show everything stored about a key");
+        say!("  EXISTS <key>       - Check for a key without reading it (expired keys don't count)");
+        say!("  GET <key> AS <t>   - Retrieve through a transform (upper, lower, reverse, len)");
+        say!("  MGET <key>...      - Retrieve several documents as a table");
+        say!("  COPY <from> <to> [FORCE] - Copy a document (FORCE overwrites an existing key)");
+        say!("  RENPREFIX <old> <new> - Rename keys starting with old to start with new");
+        say!("  DELMATCH <pattern> [YES] - Delete keys matching a glob (*, ?, [a-z])");
+        say!("                       after confirming (YES confirms inline, as scripts must)");
+        say!("  EDIT <key>         - Enter a multi-line value ending in a '.' line (terminal only)");
+        say!("  DELETE <key>       - Delete a document");
+        say!("  SADD <key> <member> - Add a member to the set at key");
+        say!("  SMEMBERS <key>     - List the members of a set");
+        say!("  SREM <key> <member> - Remove a member from a set");
+        say!("  LIST               - Show all keys");
+        say!("  LIST FORMAT <f>    - Print all documents as json, csv or plain (keys only)");
+        say!("  FIRST | LAST       - Show the smallest / largest key and its value");
+        say!("  SCAN CURSOR <c> COUNT <n> - List n sorted keys from cursor c (0 = done)");
+        say!("  SAMPLE <n> [SEED <s>] - Show n random documents (same seed, same sample)");
+        say!("  FINDVAL <value>    - Find keys holding exactly <value>");
+        say!("  TAIL [n]           - Show the n most recently written documents (default 10)");
+        say!("  CHANGEDSINCE <secs> - List keys updated at or after a Unix time");
+        say!("  NRANGE <min> <max> - Find keys with integer values in [min, max]");
+        say!("  SORTBY VALUE [DESC] - List documents by value, integers first");
+        say!("  TYPES              - Count values by the type they look like");
+        say!("  AGG                - Summarize integer values (count, sum, avg, min, max)");
+        say!("  FOLD <reducer> [PREFIX <p> | TAG <t>] - Combine values with concat, sum, count");
+        say!("                       or max-len and print the result");
+        say!("  GROUPS <sep>       - Count keys by prefix before <sep>");
+        say!("  TREE [sep]         - Show keys as a tree split on sep (default /)");
+        say!("  HOTKEYS [n]        - Show the n most-read keys (default 10)");
+        say!("  DIFF <data_dir>    - Compare against the database in <data_dir>");
+        say!("  DIFFFILE <path>    - Compare against a file written by save_to_file");
+        say!("  MERGE <data_dir> <strategy> - Copy keys in (keep, overwrite or newest)");
+        say!("  BEGIN              - Queue PUT and DELETE until COMMIT");
+        say!("  COMMIT             - Apply queued changes all together");
+        say!("  ROLLBACK           - Discard queued changes");
+        say!("  UNDO               - Revert the last PUT, REPLACE or DELETE");
+        say!("  REDO               - Reapply the last undone change");
+        say!("  STATS              - Show database statistics");
+        say!("  CONFIG             - Show the active configuration");
+        say!("  UPTIME             - Show how long the database has been open");
+        say!("  INFO               - Show keyspace, memory, stats, config and features in one report");
+        say!("  HEALTH             - Check internal indexes for inconsistencies");
+        say!("  VERIFY             - Check every save format restores the data exactly");
+        say!("  VERIFYFILE <path>  - Check a save file would load, listing every bad line");
+        say!("  LOGLEVEL <level>   - Log operations to stderr (off, info, debug)");
+        say!("  AUDIT [n]          - Show the last n audited operations (default {})", AUDIT_SHOWN);
+        say!("  AUDIT ON <size> [READS] - Keep the last size puts, deletes and expiries (and gets)");
+        say!("  AUDIT OFF          - Stop auditing and clear the log");
+        say!("  FLUSH              - Flush hot data to disk");
+        say!("  TOUCH <key>        - Bump update time and restart its TTL");
+        say!("  EXPIRE <key> <secs> - Delete key after secs seconds");
+        say!("  EXPIREMATCH <pattern> <secs> [JITTER <secs> [SEED <n>]] - Expire every key");
+        say!("                       matching a glob; JITTER spreads them over secs ± jitter");
+        say!("  TTL <key>          - Show time left before key expires");
+        say!("  PERSIST <key>      - Remove a key's expiry");
+        say!("  REAP               - Remove all expired keys now");
+        say!("  LINK <from> <rel> <to> - Link two keys with a relationship");
+        say!("  LINKEDTO <target>  - Show keys linking to a key");
+        say!("  SUBSCRIBE <pattern> - Print events for keys matching a glob (until UNSUBSCRIBE)");
+        say!("  UNSUBSCRIBE <pattern> - Stop printing events for pattern");
+        say!("  WATCH <key>        - Print a line whenever key changes (until UNWATCH)");
+        say!("  UNWATCH <key>      - Stop reporting changes to key");
+        say!("  TAG <key> <tag>    - Attach a tag to a key");
+        say!("  TAGGED <tag>       - List keys carrying a tag");
+        say!("  UNTAGGED           - List keys carrying no tags");
+        say!("  TAGS               - List every tag with how many keys carry it");
+        say!("  SETTINGS <prefix>  - List keys under a prefix with the type of each value");
+        say!("  EXPORTDOT <path> [ALL] - Write links as a GraphViz graph (ALL adds unlinked keys)");
+        say!("  LOAD <path>        - Load a file written by save_to_file");
+        say!("  RESERVE <n>        - Make hot tier room for n documents before a big LOAD");
+        say!("  EXPORT <path> [PREFIX <p> | TAG <t>] - Write documents to a JSON file");
+        say!("  PROMOTE <key>      - Move document to hot tier");
+        say!("  DEMOTE <key>       - Move document to cold tier");
+        say!("  COMPLETE [partial] - Suggest commands starting with partial");
+        say!("  COMPLETE <cmd> <partial> - Suggest keys for a key command");
+        say!("  DRYRUN ON|OFF      - Preview DELETE, DELMATCH, RENPREFIX, MERGE, FLUSH and REAP");
+        say!("  PLAIN ON|OFF       - Print ASCII tags instead of emoji");
+        say!("  STRICT ON|OFF      - End the session with exit status 1 at the first failed");
+        say!("                       command (a GET of a missing key counts), for scripts");
+        say!("  MAXDISPLAY <n>     - Cut displayed values short after n characters (0 = no limit)");
+        say!("  LASTERR            - Show the last failed terminal read or write");
+        say!("  TIME <command...>  - Run a command and show how long it took");
+        say!("  ALIAS <name> <cmd> - Define a shortcut; $1, $2... in cmd take its arguments");
+        say!("  ALIASES            - List aliases");
+        say!("  UNALIAS <name>     - Remove an alias");
+        say!("  RATELIMIT <n>      - Run at most n commands per second (0 turns it off)");
+        say!("  PING               - Reply PONG, to check the shell is responding");
+        say!("  VERSION            - Show the BurrowDB version");
+        say!("  HELP               - Show this help (.help lists meta-commands)");
+        say!("  EXIT               - Quit the program");
+    }
+
+    fn handle_meta_help(&self) {
+        say!("Meta-commands (session control, never data):");
+        say!("  .help              - Show this help");
+        say!("  .stats             - Show database statistics");
+        say!("  .config            - Show the active configuration");
+        say!("  .health            - Check internal indexes for inconsistencies");
+        say!("  .dryrun on|off     - Preview destructive commands instead of running them");
+        say!("  .plain on|off      - Print ASCII tags instead of emoji");
+        say!("  .loglevel <level>  - Log operations to stderr (off, info, debug)");
+        say!("  .exit | .quit      - Quit the program");
+    }
+
+    fn handle_exit(&mut self) {
+        if let Some(staged) = self.staged.take() {
+            say!("⚠️  Discarding {} uncommitted changes", staged.len());
+        }
+        say!("👋 Goodbye from BurrowDB!");
+    }
+
+    // Pure presentation methods - no database logic
+    fn print_put_success(&self, key: &str, value: &str) {
+        say!("✓ Stored: {} = {}", key, self.shown(value.as_bytes()));
+    }
+
+    fn print_get_result(&self, key: &str, value: &[u8]) {
+        say!("📄 {}: {}", key, self.shown(value));
+    }
+
+    /// `value` as text to display, cut short after MAXDISPLAY characters
+    fn shown(&self, value: &[u8]) -> String {
+        let text = String::from_utf8_lossy(value);
+        match self.max_display {
+            Some(limit) if text.chars().count() > limit => {
+                let total = text.chars().count();
+                let kept: String = text.chars().take(limit).collect();
+                format!("{}…(truncated, {} total)", kept, total)
+            }
+            _ => text.into_owned(),
+        }
+    }
+
+    fn print_candidates(&self, candidates: &[&str]) {
+        if candidates.is_empty() {
+            say!("(no matches)");
+        } else {
+            say!("{}", candidates.join("  "));
+        }
+    }
+
+    fn print_diff(&self, diff: &Diff, baseline: &str) {
+        if diff.is_empty() {
+            say!("✓ No differences from {}", baseline);
+            return;
+        }
+
+        say!(
+            "🔍 Differences from {} ({} added, {} removed, {} changed):",
+            baseline,
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len()
+        );
+        for key in &diff.added {
+            say!("  + {}", key);
+        }
+        for key in &diff.removed {
+            say!("  - {}", key);
+        }
+        for change in &diff.changed {
+            say!(
+                "  ~ {}: {} → {}",
+                change.key,
+                self.shown(&change.old),
+                self.shown(&change.new)
+            );
+        }
+    }
+}
+
+/// A seed for SAMPLE when the user didn't give one
+fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Write and flush `text` to stdout, keeping any failure in `OUTPUT_ERROR`
+///
+/// `print!` panics once stdout is closed, as with `burrow_db | head`.
+fn write_stdout(text: &str) {
+    let mut stdout = io::stdout().lock();
+    if let Err(e) = stdout.write_all(text.as_bytes()).and_then(|()| stdout.flush()) {
+        OUTPUT_ERROR.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert(e);
+    }
+}
+
+/// Turn plain ASCII output on or off for every CLI in the process
+///
+/// There is no color to strip; plain output only swaps emoji for ASCII.
+pub fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+/// `text` as it should be printed: unchanged, or in PLAIN mode with
+/// `PLAIN_SYMBOLS` swapped for their ASCII stand-ins
+pub fn styled(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() || !PLAIN.load(Ordering::Relaxed) {
+        return Cow::Borrowed(text);
+    }
+
+    // Emoji presentation selectors would otherwise be left behind
+    let mut plain = text.replace('\u{fe0f}', "");
+    for (symbol, ascii) in PLAIN_SYMBOLS {
+        while let Some(start) = plain.find(symbol) {
+            let end = start + symbol.len();
+            let spaced = plain[end..].starts_with(' ');
+            let end = end + plain[end..].len() - plain[end..].trim_start_matches(' ').len();
+            if spaced && !ascii.is_empty() {
+                plain.replace_range(start..end, &format!("{} ", ascii));
+            } else {
+                plain.replace_range(start..end, ascii);
+            }
+        }
+    }
+    Cow::Owned(plain)
+}
+
+/// Print a line for `event` if it concerns a watched key
+fn report_watched(watched: &Mutex<HashSet<String>>, event: &Event) {
+    let watched = watched.lock().unwrap_or_else(|e| e.into_inner());
+    match event {
+        Event::Put { key, value } if watched.contains(key) => {
+            say!("👀 {} changed: {}", key, String::from_utf8_lossy(value));
+        }
+        Event::Deleted { key } if watched.contains(key) => say!("👀 {} deleted", key),
+        Event::Expired { key, .. } if watched.contains(key) => say!("👀 {} expired", key),
+        _ => {}
+    }
+}
+
+/// Split a `RAWPUT <key> <value>` line into its key and verbatim value
+///
+/// A single space ends the key; everything after it, further spaces
+/// included, is the value. `None` for any other command, or a RAWPUT
+/// without a value.
+fn split_raw_put(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim_start().strip_prefix("RAWPUT ")?;
+    rest.trim_start_matches(' ').split_once(' ').filter(|(key, _)| !key.is_empty())
+}
+
+/// The only character in `text`, if it is exactly one character long
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// Check that an alias template runs a built-in command
+///
+/// TIME runs the rest of its line as a command, so it is looked through:
+/// a template like `TIME x` would expand `x` again, and if `x` is the alias
+/// itself, forever. Since no alias can be named after a built-in, a
+/// template that passes can never set off another expansion.
+fn check_template(template: &str) -> std::result::Result<(), String> {
+    let mut words = template.split_whitespace().skip_while(|word| *word == "TIME");
+    match words.next() {
+        Some(command) if COMMANDS.contains(&command) => Ok(()),
+        Some(command) => Err(format!("An alias must run a command, not '{}'", command)),
+        None => Err("An alias must run a command, not just TIME".to_string()),
+    }
+}
+
+/// Fill `$1`, `$2`... in an alias template with its arguments
+///
+/// Arguments no placeholder asks for are appended, so `ALIAS g GET` still
+/// works as `g key`. Too few arguments is an error rather than leaving a
+/// literal `$2` in the command.
+fn expand_alias(template: &str, args: &[&str]) -> std::result::Result<String, String> {
+    let mut used = vec![false; args.len()];
+    let mut words = Vec::new();
+
+    for word in template.split_whitespace() {
+        match word.strip_prefix('$').and_then(|n| n.parse::<usize>().ok()) {
+            Some(n) if n >= 1 => {
+                let arg = args
+                    .get(n - 1)
+                    .ok_or_else(|| format!("needs at least {} arguments", n))?;
+                used[n - 1] = true;
+                words.push(*arg);
+            }
+            _ => words.push(word),
+        }
+    }
+
+    words.extend(args.iter().zip(&used).filter(|(_, used)| !**used).map(|(arg, _)| *arg));
+    Ok(words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_db(temp_dir: &TempDir) -> BurrowDB {
+        BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap()
+    }
+
+    #[test]
+    fn test_edit_is_refused_without_a_terminal() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        db.put_raw("note".to_string(), b"old".to_vec()).unwrap();
+
+        let mut cli = CLI::new(&mut db).interactive(false);
+        cli.handle_command("EDIT note");
+        cli.handle_command("EDIT fresh");
+        assert_eq!(db.get("note").unwrap().unwrap(), b"old");
+        assert!(!db.contains_key("fresh"));
+    }
+
+    #[test]
+    fn test_delmatch_confirms_inline_without_a_terminal() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        for key in ["tmp:1", "tmp:2", "keep"] {
+            db.put_raw(key.to_string(), b"1".to_vec()).unwrap();
+        }
+
+        let mut cli = CLI::new(&mut db).interactive(false);
+        cli.handle_command("DELMATCH tmp:*");
+        assert_eq!(cli.db.keys().unwrap().len(), 3);
+        cli.handle_command("DELMATCH tmp:* YES");
+        assert_eq!(db.keys().unwrap(), vec!["keep".to_string()]);
+    }
+
+    #[test]
+    fn test_put_suffixes() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir).with_overwrite_protection(true);
+        let mut cli = CLI::new(&mut db);
+        cli.handle_command("PUT a 1");
+        cli.handle_command("PUT a 2");
+        assert_eq!(cli.db.get("a").unwrap().unwrap(), b"1");
+        cli.handle_command("PUT a 2 FORCE");
+        assert_eq!(cli.db.get("a").unwrap().unwrap(), b"2");
+
+        let later = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 600;
+        cli.handle_command(&format!("PUT t x  y EXAT {}", later));
+        assert_eq!(cli.db.get("t").unwrap().unwrap(), b"x y");
+        assert!(cli.db.ttl("t").is_some());
+        cli.handle_command("PUT bad x EXAT soon");
+        assert!(!cli.db.contains_key("bad"));
+        cli.handle_command("RAWPUT r x  EXAT 5");
+        assert_eq!(cli.db.get("r").unwrap().unwrap(), b"x  EXAT 5");
+        assert_eq!(cli.db.ttl("r"), None);
+
+        // Unprotected, there is nothing to force
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        let mut cli = CLI::new(&mut db);
+        cli.handle_command("PUT a use FORCE");
+        assert_eq!(cli.db.get("a").unwrap().unwrap(), b"use FORCE");
+    }
+
+    #[test]
+    fn test_split_raw_put() {
+        assert_eq!(split_raw_put("RAWPUT k  two  spaces "), Some(("k", " two  spaces ")));
+        assert_eq!(split_raw_put("  RAWPUT   k v"), Some(("k", "v")));
+        assert_eq!(split_raw_put("RAWPUT k "), Some(("k", "")));
+        assert_eq!(split_raw_put("RAWPUT k"), None);
+        assert_eq!(split_raw_put("PUT k v"), None);
+    }
+
+    #[test]
+    fn test_expand_alias() {
+        assert_eq!(expand_alias("GET", &["k"]).unwrap(), "GET k");
+        assert_eq!(expand_alias("COPY $2 $1", &["a", "b"]).unwrap(), "COPY b a");
+        assert_eq!(expand_alias("PUT $1", &["k", "some", "value"]).unwrap(), "PUT k some value");
+        assert_eq!(expand_alias("GET $2", &["a"]).unwrap_err(), "needs at least 2 arguments");
+        assert_eq!(expand_alias("PUT $0", &[]).unwrap(), "PUT $0");
+    }
+
+    #[test]
+    fn test_alias_cannot_expand_into_an_alias() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        let mut cli = CLI::new(&mut db);
+        cli.handle_command("ALIAS x TIME x");
+        cli.handle_command("ALIAS y TIME TIME GET");
+        cli.handle_command("ALIAS z TIME y");
+        cli.handle_command("ALIAS t TIME PUT $1 1");
+        assert_eq!(cli.aliases.keys().collect::<Vec<_>>(), ["t", "y"]);
+
+        // Running them is safe, rather than recursing forever
+        cli.handle_command("x");
+        cli.handle_command("t k");
+        assert_eq!(cli.db.get("k").unwrap().unwrap(), b"1");
+
+        let path = temp_dir.path().join("aliases");
+        fs::write(&path, "x\tTIME x\ng\tGET\n").unwrap();
+        let other_dir = TempDir::new().unwrap();
+        let mut db = test_db(&other_dir);
+        let cli = CLI::new(&mut db).aliases_file(&path);
+        assert_eq!(cli.aliases.keys().collect::<Vec<_>>(), ["g"]);
+    }
+
+    #[test]
+    fn test_transaction_stages_until_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        db.put_raw("old".to_string(), b"1".to_vec()).unwrap();
+        let mut cli = CLI::new(&mut db);
+
+        cli.handle_command("BEGIN");
+        cli.handle_command("PUT new 2");
+        cli.handle_command("DELETE old");
+        cli.handle_command("TOUCH old");
+        cli.handle_command("SADD s m");
+        assert_eq!(cli.staged.as_ref().map(Vec::len), Some(2));
+        assert_eq!(cli.db.keys().unwrap(), vec!["old".to_string()]);
+        assert!(cli.db.smembers("s").is_empty());
+
+        cli.handle_command("COMMIT");
+        assert!(cli.staged.is_none());
+        assert_eq!(cli.db.keys().unwrap(), vec!["new".to_string()]);
+
+        cli.handle_command("BEGIN");
+        cli.handle_command("PUT dropped 3");
+        cli.handle_command("ROLLBACK");
+        assert!(!cli.db.contains_key("dropped"));
+
+        // The commit is undone one change at a time, newest first
+        cli.handle_command("UNDO");
+        assert_eq!(cli.db.get("old").unwrap().unwrap(), b"1");
+        cli.handle_command("UNDO");
+        assert_eq!(cli.db.keys().unwrap(), vec!["old".to_string()]);
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        let mut cli = CLI::new(&mut db);
+        cli.handle_command("PUT a 1");
+        cli.handle_command("PUT a 2");
+
+        cli.handle_command("UNDO");
+        assert_eq!(cli.db.get("a").unwrap().unwrap(), b"1");
+        cli.handle_command("UNDO");
+        assert!(!cli.db.contains_key("a"));
+        cli.handle_command("REDO");
+        assert_eq!(cli.db.get("a").unwrap().unwrap(), b"1");
+
+        // A new change forgets what was undone
+        cli.handle_command("DELETE a");
+        cli.handle_command("REDO");
+        assert!(!cli.db.contains_key("a"));
+        assert!(cli.redo.is_empty());
+
+        for n in 0..UNDO_DEPTH + 5 {
+            cli.handle_command(&format!("PUT n {}", n));
+        }
+        assert_eq!(cli.undo.len(), UNDO_DEPTH);
+    }
+
+    #[test]
+    fn test_styled() {
+        assert!(matches!(styled("✓ Stored: a = 1"), Cow::Borrowed(_)));
+
+        set_plain(true);
+        let ok = styled("✓ Stored: a = é");
+        let doc = styled("📄 a: 1");
+        let warn = styled("⚠️  Discarding 2 changes");
+        let audit = styled("📜 Last 2 of 5 audited operations:");
+        let info = styled("ℹ️  Database Info:");
+        let jitter = styled("⏳ 3 keys matching a* expire in 60s ± 5s");
+        set_plain(false);
+        assert_eq!(ok, "[ok] Stored: a = é");
+        assert_eq!(doc, "a: 1");
+        assert_eq!(warn, "[warn] Discarding 2 changes");
+        assert_eq!(audit, "Last 2 of 5 audited operations:");
+        assert_eq!(info, "Database Info:");
+        assert_eq!(jitter, "3 keys matching a* expire in 60s +/- 5s");
+    }
+}
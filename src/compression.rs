@@ -0,0 +1,120 @@
+//! Inline LZ-style compression for large hot tier values
+//!
+//! A deliberately small scheme so the crate stays dependency-free. The
+//! output is a sequence of tokens, each starting with a control byte:
+//!
+//! ```text
+//! 0xxxxxxx               literal run: the next x+1 bytes are copied as-is
+//! 1xxxxxxx offset:u16le  match: copy x+4 bytes starting `offset` bytes back
+//! ```
+//!
+//! Matches may overlap the bytes they produce, so long runs of one byte
+//! compress to a literal and a chain of offset-1 matches.
+
+const MIN_MATCH: usize = 4;
+const MAX_MATCH: usize = 0x7f + MIN_MATCH;
+const MAX_LITERAL: usize = 0x80;
+const MAX_OFFSET: usize = u16::MAX as usize;
+const HASH_BITS: u32 = 12;
+
+/// Compress `input`; the result may be larger for incompressible data
+pub(crate) fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() / 2);
+    // Most recent position of each hashed 4-byte sequence
+    let mut recent = vec![usize::MAX; 1 << HASH_BITS];
+    let mut literal_start = 0;
+    let mut i = 0;
+
+    while i + MIN_MATCH <= input.len() {
+        let slot = hash(&input[i..i + MIN_MATCH]);
+        let candidate = recent[slot];
+        recent[slot] = i;
+
+        let found = candidate != usize::MAX
+            && i - candidate <= MAX_OFFSET
+            && input[candidate..candidate + MIN_MATCH] == input[i..i + MIN_MATCH];
+        if !found {
+            i += 1;
+            continue;
+        }
+
+        let mut len = MIN_MATCH;
+        while len < MAX_MATCH && i + len < input.len() && input[candidate + len] == input[i + len] {
+            len += 1;
+        }
+
+        push_literals(&mut out, &input[literal_start..i]);
+        out.push(0x80 | (len - MIN_MATCH) as u8);
+        out.extend_from_slice(&((i - candidate) as u16).to_le_bytes());
+        i += len;
+        literal_start = i;
+    }
+
+    push_literals(&mut out, &input[literal_start..]);
+    out
+}
+
+/// Reverse `compress`, or `None` if `input` is not valid compressed data
+pub(crate) fn decompress(input: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() * 2);
+    let mut i = 0;
+
+    while i < input.len() {
+        let control = input[i];
+        i += 1;
+
+        if control & 0x80 == 0 {
+            let len = control as usize + 1;
+            out.extend_from_slice(input.get(i..i + len)?);
+            i += len;
+        } else {
+            let len = (control & 0x7f) as usize + MIN_MATCH;
+            let offset = u16::from_le_bytes(input.get(i..i + 2)?.try_into().ok()?) as usize;
+            i += 2;
+            if offset == 0 || offset > out.len() {
+                return None;
+            }
+
+            let start = out.len() - offset;
+            for k in 0..len {
+                out.push(out[start + k]);
+            }
+        }
+    }
+
+    Some(out)
+}
+
+fn push_literals(out: &mut Vec<u8>, literals: &[u8]) {
+    for chunk in literals.chunks(MAX_LITERAL) {
+        out.push((chunk.len() - 1) as u8);
+        out.extend_from_slice(chunk);
+    }
+}
+
+fn hash(bytes: &[u8]) -> usize {
+    let word = u32::from_le_bytes(bytes.try_into().unwrap());
+    (word.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let text = "the quick brown fox jumps over the lazy dog. ".repeat(50);
+        let runs = vec![b'z'; 1000];
+        let mixed: Vec<u8> = (0..3000u32).map(|i| (i * 7 % 251) as u8).collect();
+
+        for input in [text.as_bytes(), &runs[..], &mixed[..], b"", b"abc"] {
+            assert_eq!(decompress(&compress(input)).unwrap(), input);
+        }
+        assert!(compress(text.as_bytes()).len() < text.len() / 5);
+        assert!(compress(&runs).len() < 40);
+
+        // A match reaching back before the start of the output is rejected
+        assert_eq!(decompress(&[0x80, 0x05, 0x00]), None);
+        assert_eq!(decompress(&[0x03, b'a']), None);
+    }
+}
@@ -0,0 +1,207 @@
+//! CSV export and import
+//!
+//! Files have a `key,value` header row followed by one row per document.
+//! Fields containing commas, quotes, line breaks or surrounding spaces are
+//! quoted, with embedded quotes doubled, as in RFC 4180. Like the JSON
+//! export, values are treated as UTF-8 text; invalid byte sequences are
+//! replaced with U+FFFD when exported.
+
+use crate::error::{BurrowError, Result};
+use crate::BurrowDB;
+use std::io::{Read, Write};
+
+const HEADER: [&str; 2] = ["key", "value"];
+
+impl BurrowDB {
+    /// Write every document as CSV
+    ///
+    /// Cold documents are read one at a time, as with `write_jsonl`.
+    pub fn write_csv<W: Write>(&self, w: &mut W) -> Result<()> {
+        w.write_all(b"key,value\n")?;
+        self.for_each_entry(|key, value| {
+            let mut row = String::new();
+            push_csv_field(key, &mut row);
+            row.push(',');
+            push_csv_field(&String::from_utf8_lossy(value), &mut row);
+            row.push('\n');
+
+            w.write_all(row.as_bytes())?;
+            Ok(())
+        })
+    }
+
+    /// Load documents from CSV written by `write_csv`
+    ///
+    /// The whole input is parsed before anything is stored. Returns the
+    /// number of documents loaded.
+    pub fn read_csv<R: Read>(&mut self, mut r: R) -> Result<usize> {
+        let mut text = String::new();
+        r.read_to_string(&mut text)?;
+        let entries = parse_csv_records(&text)?;
+        self.bulk_load(entries)
+    }
+
+    /// Like `read_csv`, but refuse to overwrite existing keys
+    ///
+    /// If any key in the input is already stored, nothing is loaded and
+    /// this fails with `KeysExist`.
+    pub fn read_csv_strict<R: Read>(&mut self, mut r: R) -> Result<usize> {
+        let mut text = String::new();
+        r.read_to_string(&mut text)?;
+        let entries = parse_csv_records(&text)?;
+        self.reject_existing(&entries)?;
+        self.bulk_load(entries)
+    }
+}
+
+/// Parse a CSV document with a `key,value` header into its records
+pub(crate) fn parse_csv_records(text: &str) -> Result<Vec<(String, Vec<u8>)>> {
+    let rows = parse_rows(text).map_err(BurrowError::SerializationError)?;
+    let mut rows = rows.into_iter();
+
+    match rows.next() {
+        Some(header) if header == HEADER => {}
+        _ => {
+            return Err(BurrowError::SerializationError(
+                "missing \"key,value\" header row".to_string(),
+            ))
+        }
+    }
+
+    rows.enumerate()
+        .map(|(index, row)| match <[String; 2]>::try_from(row) {
+            Ok([key, value]) => Ok((key, value.into_bytes())),
+            Err(row) => Err(BurrowError::SerializationError(format!(
+                "record {}: expected 2 fields, found {}",
+                index + 1,
+                row.len()
+            ))),
+        })
+        .collect()
+}
+
+/// Append `text` to `out` as a CSV field, quoting it if needed
+fn push_csv_field(text: &str, out: &mut String) {
+    let needs_quotes = text.contains([',', '"', '\n', '\r'])
+        || text.starts_with(' ')
+        || text.ends_with(' ');
+    if !needs_quotes {
+        out.push_str(text);
+        return;
+    }
+
+    out.push('"');
+    out.push_str(&text.replace('"', "\"\""));
+    out.push('"');
+}
+
+/// Split CSV text into rows of fields, skipping empty lines
+fn parse_rows(text: &str) -> std::result::Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut chars = text.chars().peekable();
+
+    loop {
+        // At the start of a field
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err(format!("row {}: unterminated quoted field", rows.len() + 1)),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if matches!(c, ',' | '\n' | '\r') {
+                    break;
+                }
+                if c == '"' {
+                    return Err(format!("row {}: quote inside unquoted field", rows.len() + 1));
+                }
+                field.push(c);
+                chars.next();
+            }
+        }
+
+        row.push(std::mem::take(&mut field));
+        match chars.next() {
+            Some(',') => continue,
+            Some('\r') if chars.peek() == Some(&'\n') => {
+                chars.next();
+            }
+            Some('\n') | Some('\r') | None => {}
+            Some(_) => return Err(format!("row {}: text after closing quote", rows.len() + 1)),
+        }
+
+        let ended = chars.peek().is_none();
+        let row_is_blank = row.len() == 1 && row[0].is_empty();
+        if !row_is_blank {
+            rows.push(std::mem::take(&mut row));
+        } else {
+            row.clear();
+        }
+        if ended {
+            return Ok(rows);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_csv_roundtrip_with_quoting() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        db.put_raw("plain".to_string(), b"value".to_vec()).unwrap();
+        db.put_raw("a,b".to_string(), b"say \"hi\"\nbye".to_vec()).unwrap();
+        db.put_raw("padded".to_string(), b" x ".to_vec()).unwrap();
+
+        let mut out = Vec::new();
+        db.write_csv(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("key,value\n"));
+        assert!(text.contains("\"a,b\",\"say \"\"hi\"\"\nbye\"\n"));
+
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100).unwrap();
+        assert_eq!(restored.read_csv(text.as_bytes()).unwrap(), 3);
+        assert_eq!(restored.get("a,b").unwrap().unwrap(), b"say \"hi\"\nbye");
+        assert_eq!(restored.get("padded").unwrap().unwrap(), b" x ");
+
+        assert!(parse_csv_records("k,v\na,1\n").is_err());
+        assert!(parse_csv_records("key,value\na\n").is_err());
+        assert!(parse_csv_records("key,value\n\"a,1\n").is_err());
+        assert_eq!(parse_csv_records("key,value\r\n\r\na,\r\n").unwrap(), vec![("a".to_string(), vec![])]);
+    }
+
+    #[test]
+    fn test_read_csv_strict_names_first_conflicts() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        let mut text = String::from("key,value\nnew,1\n");
+        for i in 0..12 {
+            db.put_raw(format!("k{:02}", i), b"mine".to_vec()).unwrap();
+            text.push_str(&format!("k{:02},theirs\n", i));
+        }
+
+        match db.read_csv_strict(text.as_bytes()) {
+            Err(BurrowError::KeysExist { keys, total }) => {
+                assert_eq!(keys.len(), crate::MAX_REPORTED_CONFLICTS);
+                assert_eq!(keys[0], "k00");
+                assert_eq!(total, 12);
+            }
+            other => panic!("expected KeysExist, got {:?}", other),
+        }
+        assert!(!db.contains_key("new"));
+        assert_eq!(db.get("k05").unwrap().unwrap(), b"mine");
+    }
+}
@@ -0,0 +1,127 @@
+use crate::error::Result;
+use crate::text_format::decode_text;
+use crate::BurrowDB;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// A key whose value differs between two databases
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub key: String,
+    /// Value in the database being compared against
+    pub old: Vec<u8>,
+    /// Value in this database
+    pub new: Vec<u8>,
+}
+
+/// Differences between a database and a baseline, with keys sorted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diff {
+    /// Keys only present in this database
+    pub added: Vec<String>,
+    /// Keys only present in the baseline
+    pub removed: Vec<String>,
+    /// Keys present in both with different values
+    pub changed: Vec<Change>,
+}
+
+impl Diff {
+    /// Compare two sets of entries, treating `baseline` as the old state
+    pub(crate) fn between(
+        current: Vec<(String, Vec<u8>)>,
+        baseline: Vec<(String, Vec<u8>)>,
+    ) -> Self {
+        let mut baseline: HashMap<String, Vec<u8>> = baseline.into_iter().collect();
+        let mut diff = Diff::default();
+
+        for (key, new) in current {
+            match baseline.remove(&key) {
+                None => diff.added.push(key),
+                Some(old) if old != new => diff.changed.push(Change { key, old, new }),
+                Some(_) => {}
+            }
+        }
+        diff.removed = baseline.into_keys().collect();
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort_by(|a, b| a.key.cmp(&b.key));
+        diff
+    }
+
+    /// Whether the two sides were identical
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl BurrowDB {
+    /// Compare this database against `other` across both tiers
+    ///
+    /// `other` is the baseline: keys only here are `added`, keys only in
+    /// `other` are `removed`, and `changed` carries both values.
+    pub fn diff(&self, other: &BurrowDB) -> Result<Diff> {
+        Ok(Diff::between(self.entries()?, other.entries()?))
+    }
+
+    /// Compare this database against a file written by `save_to_file`
+    ///
+    /// The file is the baseline, as `other` is for `diff`: `added` keys
+    /// haven't been saved there. Its records are parsed directly (checksum
+    /// verified) rather than loaded into a second database.
+    pub fn diff_file<P: AsRef<Path>>(&self, path: P) -> Result<Diff> {
+        let baseline = decode_text(&fs::read_to_string(path)?)?;
+        Ok(Diff::between(self.entries()?, baseline))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entries(pairs: &[(&str, &str)]) -> Vec<(String, Vec<u8>)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_between() {
+        let current = entries(&[("a", "1"), ("b", "2"), ("d", "4")]);
+        let baseline = entries(&[("a", "1"), ("b", "old"), ("c", "3")]);
+
+        let diff = Diff::between(current, baseline);
+        assert_eq!(diff.added, vec!["d".to_string()]);
+        assert_eq!(diff.removed, vec!["c".to_string()]);
+        assert_eq!(diff.changed, vec![Change {
+            key: "b".to_string(),
+            old: b"old".to_vec(),
+            new: b"2".to_vec(),
+        }]);
+    }
+
+    #[test]
+    fn test_diff_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("saved.txt");
+        let mut db = BurrowDB::with_config(temp_dir.path().join("db").to_str().unwrap(), 100).unwrap();
+        db.put_raw("kept".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("edited".to_string(), b"old".to_vec()).unwrap();
+        db.put_raw("dropped".to_string(), b"x".to_vec()).unwrap();
+        db.save_to_file(&file).unwrap();
+        assert!(db.diff_file(&file).unwrap().is_empty());
+
+        db.put_raw("edited".to_string(), b"new".to_vec()).unwrap();
+        db.put_raw("unsaved".to_string(), b"y".to_vec()).unwrap();
+        db.delete("dropped").unwrap();
+
+        let diff = db.diff_file(&file).unwrap();
+        assert_eq!(diff.added, vec!["unsaved".to_string()]);
+        assert_eq!(diff.removed, vec!["dropped".to_string()]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].old, b"old");
+    }
+}
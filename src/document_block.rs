@@ -1,5 +1,7 @@
+use crate::compression;
 use crate::error::Result;
 use crate::generated::document_generated::burrow_db::schema::get_root_as_document_block;
+use std::borrow::Cow;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Wrapper around FlatBuffer data representing a document block
@@ -7,13 +9,18 @@ use std::time::{SystemTime, UNIX_EPOCH};
 /// This is a pure FlatBuffers implementation - no JSON conversion.
 /// Clients must handle their own serialization format.
 pub struct DocumentBlock {
-    /// Serialized FlatBuffer bytes
+    /// Serialized FlatBuffer bytes, compressed when `original_len` is set
     data: Vec<u8>,
+    /// Length of the uncompressed bytes, if `data` holds the output of
+    /// `compression::compress`
+    original_len: Option<usize>,
 
     /// Access tracking metadata (mutable, not in FlatBuffer)
     pub access_count: u32,
     pub last_accessed: u64,
     pub is_hot: bool,
+    /// When the value was last written or touched (Unix seconds)
+    pub updated_at: u64,
 }
 
 impl DocumentBlock {
@@ -36,9 +43,11 @@ impl DocumentBlock {
 
         Ok(Self {
             data: flatbuffer_bytes,
+            original_len: None,
             access_count,
             last_accessed: now,
             is_hot,
+            updated_at: now,
         })
     }
 
@@ -54,20 +63,61 @@ impl DocumentBlock {
 
         Self {
             data,
+            original_len: None,
             access_count: 0,
             last_accessed: now,
             is_hot: true,
+            updated_at: now,
         }
     }
 
-    /// Get the raw FlatBuffer bytes
+    /// Get the bytes as held in memory
+    ///
+    /// These are compressed if `is_compressed` is set; use `value` for the
+    /// original document.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Get the document's original bytes, decompressing if needed
     ///
     /// Returns the complete FlatBuffer for:
     /// - Disk storage
     /// - Network transmission
     /// - Client-side deserialization
-    pub fn as_bytes(&self) -> &[u8] {
-        &self.data
+    pub fn value(&self) -> Cow<'_, [u8]> {
+        if self.is_compressed() {
+            Cow::Owned(decompress(&self.data))
+        } else {
+            Cow::Borrowed(&self.data)
+        }
+    }
+
+    /// Whether the bytes are held compressed
+    pub fn is_compressed(&self) -> bool {
+        self.original_len.is_some()
+    }
+
+    /// Length of the original document, without decompressing it
+    pub fn value_len(&self) -> usize {
+        self.original_len.unwrap_or(self.data.len())
+    }
+
+    /// Compress the bytes if there are more than `threshold` of them and
+    /// compression actually makes them smaller
+    ///
+    /// Only the database's own hot tier does this, so blocks callers build
+    /// or load with `Storage::load` are never compressed and `key` and
+    /// `size_bytes` can read their bytes in place.
+    pub(crate) fn compress_above(&mut self, threshold: usize) {
+        if self.is_compressed() || self.data.len() <= threshold {
+            return;
+        }
+        let packed = compression::compress(&self.data);
+        if packed.len() < self.data.len() {
+            self.original_len = Some(self.data.len());
+            self.data = packed;
+        }
     }
 
     /// Get a mutable reference to the raw bytes (for access tracking updates)
@@ -76,6 +126,17 @@ impl DocumentBlock {
         &self.data
     }
 
+    /// Get mutable access to the stored bytes for in-place updates
+    ///
+    /// Compressed bytes are decompressed first, so callers always see the
+    /// original document.
+    pub fn data_mut(&mut self) -> &mut Vec<u8> {
+        if self.original_len.take().is_some() {
+            self.data = decompress(&self.data);
+        }
+        &mut self.data
+    }
+
     /// Get the key of this document
     pub fn key(&self) -> &str {
         let doc_block = get_root_as_document_block(&self.data);
@@ -88,6 +149,14 @@ impl DocumentBlock {
         doc_block.metadata().size_bytes()
     }
 
+    /// Mark the document as updated now without changing its contents
+    pub fn touch(&mut self) {
+        self.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+    }
+
     /// Record an access to this document (for hot-cold tiering)
     pub fn record_access(&mut self) {
         self.access_count += 1;
@@ -96,4 +165,9 @@ impl DocumentBlock {
             .unwrap()
             .as_secs();
     }
-}
\ No newline at end of file
+}
+
+/// Decompress bytes this module compressed itself
+fn decompress(data: &[u8]) -> Vec<u8> {
+    compression::decompress(data).expect("compressed block data is always valid")
+}
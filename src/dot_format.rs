@@ -0,0 +1,100 @@
+//! GraphViz export of links
+//!
+//! Each key is a node and each link an edge labelled with its relationship,
+//! so `dot -Tsvg` draws the relationship structure. Nodes and edges are
+//! sorted, so exports of the same links are identical.
+
+use crate::error::Result;
+use crate::BurrowDB;
+use std::collections::BTreeSet;
+
+/// Options for `export_dot_with`
+#[derive(Debug, Clone, Default)]
+pub struct DotFormat {
+    /// Also list keys that have no links, as unconnected nodes
+    pub include_isolated: bool,
+}
+
+impl BurrowDB {
+    /// Every link as a GraphViz `digraph`, leaving out keys with no links
+    pub fn export_dot(&self) -> String {
+        self.render_dot(&BTreeSet::new())
+    }
+
+    /// Export links using the given options
+    ///
+    /// Listing isolated keys reads every key, so unlike `export_dot` this
+    /// can fail on the cold tier.
+    pub fn export_dot_with(&self, format: &DotFormat) -> Result<String> {
+        let mut isolated = BTreeSet::new();
+        if format.include_isolated {
+            isolated.extend(self.keys()?);
+        }
+        Ok(self.render_dot(&isolated))
+    }
+
+    /// Render the links, plus a node line for each of `extra_nodes`
+    fn render_dot(&self, extra_nodes: &BTreeSet<String>) -> String {
+        let mut links: Vec<(&str, &str, &str)> = self.links.iter().collect();
+        links.sort_unstable();
+
+        let mut nodes: BTreeSet<&str> = extra_nodes.iter().map(String::as_str).collect();
+        for (from, _, to) in &links {
+            nodes.insert(from);
+            nodes.insert(to);
+        }
+
+        let mut out = String::from("digraph burrow {\n");
+        for node in nodes {
+            out.push_str(&format!("    {};\n", quote(node)));
+        }
+        for (from, rel, to) in links {
+            out.push_str(&format!("    {} -> {} [label={}];\n", quote(from), quote(to), quote(rel)));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// A GraphViz quoted ID
+fn quote(id: &str) -> String {
+    let mut quoted = String::with_capacity(id.len() + 2);
+    quoted.push('"');
+    for c in id.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_dot() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        for key in ["order1", "user \"1\"", "loner"] {
+            db.put_raw(key.to_string(), b"v".to_vec()).unwrap();
+        }
+        db.link("order1", "placed_by", "user \"1\"").unwrap();
+
+        assert_eq!(db.export_dot(), "\
+digraph burrow {
+    \"order1\";
+    \"user \\\"1\\\"\";
+    \"order1\" -> \"user \\\"1\\\"\" [label=\"placed_by\"];
+}
+");
+        let all = db.export_dot_with(&DotFormat { include_isolated: true }).unwrap();
+        assert!(all.contains("    \"loner\";\n"));
+        assert_eq!(all.lines().count(), 6);
+    }
+}
@@ -0,0 +1,159 @@
+//! Passphrase-obfuscated save files
+//!
+//! This keeps casual snapshots from being readable as plain text. It is
+//! **obfuscation, not strong encryption**: the keystream comes from a
+//! non-cryptographic hash of the passphrase, and anyone willing to attack
+//! it can recover the contents. Use real encryption for data that matters.
+//!
+//! Layout:
+//!
+//! ```text
+//! b"BRWE"  version:u8  nonce:u64
+//! ciphertext (the binary save format XORed with the keystream)
+//! checksum:u64 (little-endian FNV-1a of the plaintext)
+//! ```
+//!
+//! The nonce is fresh for every save, so saving the same data twice with
+//! the same passphrase gives different files. A wrong passphrase decrypts
+//! to garbage and fails the checksum.
+
+use crate::binary_format;
+use crate::checksum::fnv1a64;
+use crate::error::{BurrowError, Result};
+use crate::text_format::write_atomic;
+use crate::BurrowDB;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAGIC: &[u8; 4] = b"BRWE";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8;
+
+impl BurrowDB {
+    /// Save every document, obfuscated with `passphrase`
+    ///
+    /// The file is replaced atomically, as with `save_to_file`. See the
+    /// module documentation for how weak the protection is.
+    pub fn save_to_file_encrypted<P: AsRef<Path>>(&self, path: P, passphrase: &str) -> Result<()> {
+        let mut data = self.encode_binary()?;
+        let checksum = fnv1a64(&data);
+        let nonce = fresh_nonce();
+        apply_keystream(&mut data, passphrase, nonce);
+
+        let mut out = Vec::with_capacity(HEADER_LEN + data.len() + 8);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&nonce.to_le_bytes());
+        out.extend_from_slice(&data);
+        out.extend_from_slice(&checksum.to_le_bytes());
+        write_atomic(path.as_ref(), &out)
+    }
+
+    /// Load documents from a file written by `save_to_file_encrypted`
+    ///
+    /// A wrong passphrase or a damaged file returns `ChecksumMismatch` and
+    /// loads nothing. Returns the number of documents loaded.
+    pub fn load_from_file_encrypted<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        passphrase: &str,
+    ) -> Result<usize> {
+        let data = fs::read(path)?;
+        if data.len() < HEADER_LEN + 8 || &data[..MAGIC.len()] != MAGIC {
+            return Err(corrupt("not a BurrowDB encrypted save file"));
+        }
+        if data[MAGIC.len()] != VERSION {
+            return Err(corrupt(&format!(
+                "unsupported encrypted format version {}",
+                data[MAGIC.len()]
+            )));
+        }
+
+        let nonce = u64::from_le_bytes(data[MAGIC.len() + 1..HEADER_LEN].try_into().unwrap());
+        let (body, trailer) = data[HEADER_LEN..].split_at(data.len() - HEADER_LEN - 8);
+        let mut plain = body.to_vec();
+        apply_keystream(&mut plain, passphrase, nonce);
+
+        let expected = u64::from_le_bytes(trailer.try_into().unwrap());
+        let found = fnv1a64(&plain);
+        if expected != found {
+            return Err(BurrowError::ChecksumMismatch { expected, found });
+        }
+
+        let entries = binary_format::decode(&plain)?;
+        self.bulk_load(entries)
+    }
+}
+
+/// XOR `data` with the keystream for `passphrase` and `nonce`
+///
+/// Applying it twice restores the original bytes.
+fn apply_keystream(data: &mut [u8], passphrase: &str, nonce: u64) {
+    let mut seed = nonce.to_le_bytes().to_vec();
+    seed.extend_from_slice(passphrase.as_bytes());
+    let mut state = fnv1a64(&seed);
+
+    for chunk in data.chunks_mut(8) {
+        let block = splitmix64(&mut state).to_le_bytes();
+        for (byte, key) in chunk.iter_mut().zip(block) {
+            *byte ^= key;
+        }
+    }
+}
+
+/// Advance `state` and return the next SplitMix64 output
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A nonce that differs between saves, even within one process
+fn fresh_nonce() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    fnv1a64(format!("{}:{}:{}", nanos, std::process::id(), count).as_bytes())
+}
+
+fn corrupt(msg: &str) -> BurrowError {
+    BurrowError::SerializationError(msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_encrypted_roundtrip_and_wrong_passphrase() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("secrets.brwe");
+        let mut db = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        db.put_raw("api-key".to_string(), b"hunter2".to_vec()).unwrap();
+        db.put_raw("note".to_string(), b"top secret".to_vec()).unwrap();
+        db.save_to_file_encrypted(&path, "correct horse").unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert!(!bytes.windows(7).any(|w| w == b"hunter2"));
+
+        let mut wrong = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100).unwrap();
+        assert!(matches!(
+            wrong.load_from_file_encrypted(&path, "battery staple"),
+            Err(BurrowError::ChecksumMismatch { .. })
+        ));
+        assert!(wrong.keys().unwrap().is_empty());
+
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("c").to_str().unwrap(), 100).unwrap();
+        assert_eq!(restored.load_from_file_encrypted(&path, "correct horse").unwrap(), 2);
+        assert_eq!(restored.get("api-key").unwrap().unwrap(), b"hunter2");
+        assert_eq!(restored.get("note").unwrap().unwrap(), b"top secret");
+    }
+}
@@ -18,6 +18,32 @@ pub enum BurrowError {
 
     /// Storage error (disk operations)
     StorageError(String),
+
+    /// Invalid database configuration
+    InvalidConfig(String),
+
+    /// Saved file failed its integrity check
+    ChecksumMismatch { expected: u64, found: u64 },
+
+    /// Value exceeds the configured maximum size
+    ValueTooLarge { size: usize, limit: usize },
+
+    /// Key exceeds the configured maximum size
+    KeyTooLarge { size: usize, limit: usize },
+
+    /// Key is already in use
+    KeyExists(String),
+
+    /// Write rejected by the validator set with `set_validator`
+    ValidationFailed(String),
+
+    /// Data directory is locked by another exclusive database
+    Locked(String),
+
+    /// A strict import found keys that are already in use
+    ///
+    /// `keys` holds the first few, sorted; `total` counts them all.
+    KeysExist { keys: Vec<String>, total: usize },
 }
 
 impl fmt::Display for BurrowError {
@@ -28,6 +54,30 @@ impl fmt::Display for BurrowError {
             BurrowError::InvalidDocument(msg) => write!(f, "Invalid document: {}", msg),
             BurrowError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
             BurrowError::StorageError(msg) => write!(f, "Storage error: {}", msg),
+            BurrowError::InvalidConfig(msg) => write!(f, "Invalid configuration: {}", msg),
+            BurrowError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "Checksum mismatch: expected {:016x}, found {:016x}",
+                expected, found
+            ),
+            BurrowError::ValueTooLarge { size, limit } => {
+                write!(f, "Value too large: {} bytes (limit {} bytes)", size, limit)
+            }
+            BurrowError::KeyTooLarge { size, limit } => {
+                write!(f, "Key too large: {} bytes (limit {} bytes)", size, limit)
+            }
+            BurrowError::KeyExists(key) => write!(f, "Key already exists: {}", key),
+            BurrowError::ValidationFailed(msg) => write!(f, "Validation failed: {}", msg),
+            BurrowError::Locked(msg) => write!(f, "Database locked: {}", msg),
+            BurrowError::KeysExist { keys, total } if keys.len() < *total => write!(
+                f,
+                "Keys already exist: {} and {} more",
+                keys.join(", "),
+                total - keys.len()
+            ),
+            BurrowError::KeysExist { keys, .. } => {
+                write!(f, "Keys already exist: {}", keys.join(", "))
+            }
         }
     }
 }
@@ -0,0 +1,168 @@
+//! Observer hook for database events
+//!
+//! Observers are called synchronously, in registration order, after the
+//! change they describe has been applied.
+
+use crate::audit::AuditOp;
+use crate::glob::glob_match;
+use crate::BurrowDB;
+use std::sync::mpsc::{self, Receiver};
+
+/// Something that happened to the data
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event {
+    /// A key was stored or modified, with its new value
+    Put { key: String, value: Vec<u8> },
+    /// A key was removed by `delete`
+    Deleted { key: String },
+    /// A key was removed because its TTL ran out, with its final value
+    Expired { key: String, value: Vec<u8> },
+}
+
+impl Event {
+    /// The key the event is about
+    pub fn key(&self) -> &str {
+        match self {
+            Event::Put { key, .. } | Event::Deleted { key } | Event::Expired { key, .. } => key,
+        }
+    }
+}
+
+/// A callback registered with `subscribe`
+///
+/// Observers must be `Send + Sync` so the database can be shared between threads.
+pub type Observer = Box<dyn Fn(&Event) + Send + Sync>;
+
+/// A callback registered with `on_evict`, given the key and its value
+pub type EvictHook = Box<dyn FnMut(&str, &[u8]) + Send + Sync>;
+
+impl BurrowDB {
+    /// Call `observer` for every future event
+    pub fn subscribe(&mut self, observer: Observer) {
+        self.observers.push(observer);
+    }
+
+    /// Receive every future event for keys matching the glob `pattern`
+    ///
+    /// Each call gets its own channel, so overlapping patterns each see a
+    /// copy of the event. Events queue until received. Dropping the
+    /// receiver stops delivery, though the observer behind it stays
+    /// registered for the life of the database.
+    pub fn subscribe_pattern(&mut self, pattern: &str) -> Receiver<Event> {
+        let pattern = self.normalize_key(pattern).into_owned();
+        let (sender, receiver) = mpsc::channel();
+        self.subscribe(Box::new(move |event| {
+            if glob_match(&pattern, event.key()) {
+                // Fails only once the receiver is gone, when nobody is listening
+                let _ = sender.send(event.clone());
+            }
+        }));
+        receiver
+    }
+
+    /// Call `hook` whenever the hot tier fills and pushes a document out
+    ///
+    /// It runs just before the document leaves the hot tier for the cold
+    /// one, so it sees every LRU eviction but never a `delete` or an
+    /// explicit `demote`. Replaces any earlier hook.
+    pub fn on_evict(&mut self, hook: EvictHook) {
+        self.evict_hook = Some(hook);
+    }
+
+    /// Deliver `event` to every observer
+    pub(crate) fn emit(&self, event: Event) {
+        for observer in &self.observers {
+            observer(&event);
+        }
+    }
+
+    /// Announce the value just stored at a normalized key
+    ///
+    /// The value is only read back if someone is listening. The put is
+    /// recorded in the audit log either way.
+    pub(crate) fn emit_put(&mut self, key: &str) {
+        self.audit(AuditOp::Put, key);
+        if self.observers.is_empty() {
+            return;
+        }
+        if let Ok(Some(value)) = self.peek(key) {
+            self.emit(Event::Put { key: key.to_string(), value });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_puts_and_deletes_are_announced() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        db.subscribe(Box::new(move |event| sink.lock().unwrap().push(event.clone())));
+
+        db.put_raw("a".to_string(), b"1".to_vec()).unwrap();
+        db.modify("a", |value| value.push(b'2')).unwrap();
+        db.delete("a").unwrap();
+        db.delete("a").unwrap();
+
+        assert_eq!(*events.lock().unwrap(), vec![
+            Event::Put { key: "a".to_string(), value: b"1".to_vec() },
+            Event::Put { key: "a".to_string(), value: b"12".to_vec() },
+            Event::Deleted { key: "a".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn test_pattern_subscribers_get_their_own_copies() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        let users = db.subscribe_pattern("user:*");
+        let everything = db.subscribe_pattern("*");
+        drop(db.subscribe_pattern("*"));
+
+        db.put_raw("user:1".to_string(), b"Alice".to_vec()).unwrap();
+        db.put_raw("order:1".to_string(), b"book".to_vec()).unwrap();
+        db.delete("user:1").unwrap();
+
+        let keys = |receiver: &Receiver<Event>| -> Vec<String> {
+            receiver.try_iter().map(|event| event.key().to_string()).collect()
+        };
+        assert_eq!(keys(&users), ["user:1", "user:1"]);
+        assert_eq!(keys(&everything), ["user:1", "order:1", "user:1"]);
+    }
+
+    #[test]
+    fn test_evict_hook_sees_only_lru_evictions() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 2).unwrap();
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&evicted);
+        db.on_evict(Box::new(move |key, value| {
+            sink.lock().unwrap().push((key.to_string(), value.to_vec()));
+        }));
+
+        db.put_raw("a".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("b".to_string(), b"2".to_vec()).unwrap();
+        db.delete("b").unwrap();
+        db.put_raw("c".to_string(), b"3".to_vec()).unwrap();
+        db.demote("c").unwrap();
+        assert!(evicted.lock().unwrap().is_empty());
+
+        db.put_raw("d".to_string(), b"4".to_vec()).unwrap();
+        db.put_raw("e".to_string(), b"5".to_vec()).unwrap();
+
+        // Access times only have a resolution of seconds, so which of the
+        // three hot documents goes is left to the tie
+        let evicted = evicted.lock().unwrap().clone();
+        assert_eq!(evicted.len(), 1);
+        let (key, value) = &evicted[0];
+        assert!(["a", "d", "e"].contains(&key.as_str()));
+        assert_eq!(db.get(key).unwrap().as_ref(), Some(value));
+    }
+}
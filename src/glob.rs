@@ -0,0 +1,118 @@
+//! Glob patterns for matching keys
+//!
+//! Supports the usual shell/Redis forms:
+//! - `*` matches any run of characters (including none)
+//! - `?` matches exactly one character
+//! - `[abc]`, `[a-z]` match one character from a set; `[^...]` or `[!...]` negate it
+//! - `\` makes the next character literal
+//!
+//! An unterminated `[` is treated as a literal character.
+
+/// Whether `text` matches `pattern` in full
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it is currently matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if let Some(advance) = match_one(&pattern, p, text[t]) {
+            p += advance;
+            t += 1;
+            continue;
+        }
+
+        if pattern.get(p) == Some(&'*') {
+            backtrack = Some((p, t));
+            p += 1;
+            continue;
+        }
+
+        // Let the last `*` swallow one more character and retry
+        match backtrack {
+            Some((star, matched)) => {
+                backtrack = Some((star, matched + 1));
+                p = star + 1;
+                t = matched + 1;
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// If the pattern element at `p` matches `c`, how many pattern chars it spans
+///
+/// Returns `None` for a mismatch, the end of the pattern, or a `*` (which
+/// the caller handles).
+fn match_one(pattern: &[char], p: usize, c: char) -> Option<usize> {
+    match *pattern.get(p)? {
+        '*' => None,
+        '?' => Some(1),
+        '\\' if p + 1 < pattern.len() => (pattern[p + 1] == c).then_some(2),
+        '[' => match match_class(pattern, p, c) {
+            Some((true, len)) => Some(len),
+            Some((false, _)) => None,
+            None => (c == '[').then_some(1),
+        },
+        literal => (literal == c).then_some(1),
+    }
+}
+
+/// Match `c` against the `[...]` class starting at `start`
+///
+/// Returns whether it matched and the class's length in pattern chars, or
+/// `None` if the class is never closed.
+fn match_class(pattern: &[char], start: usize, c: char) -> Option<(bool, usize)> {
+    let mut i = start + 1;
+    let negated = matches!(pattern.get(i), Some('^' | '!'));
+    if negated {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let member = *pattern.get(i)?;
+        if member == ']' && !first {
+            return Some((matched != negated, i + 1 - start));
+        }
+        first = false;
+
+        if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|&end| end != ']') {
+            matched |= (member..=pattern[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= member == c;
+            i += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("user:*", "user:1"));
+        assert!(glob_match("user:*", "user:"));
+        assert!(!glob_match("user:*", "users"));
+        assert!(glob_match("*:1", "order:1"));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("a*b*c", "axxbyy"));
+        assert!(glob_match("k?y", "key"));
+        assert!(!glob_match("k?y", "ky"));
+        assert!(glob_match("log:[0-9][0-9]", "log:42"));
+        assert!(!glob_match("log:[^0-9]", "log:4"));
+        assert!(glob_match("[]x]", "]"));
+        assert!(glob_match(r"star\*", "star*"));
+        assert!(!glob_match(r"star\*", "starry"));
+        assert!(glob_match("open[", "open["));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("café*", "café:1"));
+    }
+}
@@ -0,0 +1,107 @@
+//! Internal consistency checks
+//!
+//! The link and tag indexes and the expiry table are kept beside the documents and must
+//! only mention live keys. `health_check` verifies that, plus that the cold
+//! tier can still be written, so corruption is noticed before it spreads.
+
+use crate::BurrowDB;
+use std::collections::HashSet;
+
+/// Inconsistencies found by `health_check`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthReport {
+    /// One human-readable line per problem, sorted
+    pub problems: Vec<String>,
+}
+
+impl HealthReport {
+    /// Whether no problems were found
+    pub fn is_healthy(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl BurrowDB {
+    /// Verify internal invariants and report anything inconsistent
+    ///
+    /// Checks that links, tags and expiry times only refer to live keys, that
+    /// the link and tag indexes' forward and reverse maps agree, and that the data
+    /// directory is writable. Failures are reported rather than returned as
+    /// errors, so one problem doesn't hide the others.
+    pub fn health_check(&self) -> HealthReport {
+        let mut problems = Vec::new();
+
+        if let Err(e) = self.cold_storage.check_writable() {
+            problems.push(format!(
+                "data directory {} is not writable: {}",
+                self.cold_storage.data_dir().display(),
+                e
+            ));
+        }
+
+        let live: HashSet<String> = match self.keys() {
+            Ok(keys) => keys.into_iter().collect(),
+            Err(e) => {
+                problems.push(format!("cannot list keys: {}", e));
+                return HealthReport { problems };
+            }
+        };
+
+        for (from, rel, to) in self.links.iter() {
+            for (end, key) in [("starts at", from), ("points to", to)] {
+                if !live.contains(key) {
+                    problems.push(format!(
+                        "link {} -[{}]-> {} {} missing key '{}'",
+                        from, rel, to, end, key
+                    ));
+                }
+            }
+        }
+        if !self.links.is_consistent() {
+            problems.push("link index forward and reverse maps disagree".to_string());
+        }
+
+        for key in self.tags.tagged_keys() {
+            if !live.contains(key) {
+                problems.push(format!("tags set on missing key '{}'", key));
+            }
+        }
+        if !self.tags.is_consistent() {
+            problems.push("tag index forward and reverse maps disagree".to_string());
+        }
+
+        for key in self.expires_at.keys() {
+            if !live.contains(key) {
+                problems.push(format!("expiry set for missing key '{}'", key));
+            }
+        }
+
+        problems.sort();
+        HealthReport { problems }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_health_check_reports_dangling_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.put_raw("a".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("b".to_string(), b"2".to_vec()).unwrap();
+        db.link("a", "knows", "b").unwrap();
+        db.expire("b", Duration::from_secs(60));
+        assert!(db.health_check().is_healthy());
+
+        // Bypass `delete` so the link and expiry are left behind
+        db.hot_data.remove("b");
+        assert_eq!(db.health_check().problems, vec![
+            "expiry set for missing key 'b'".to_string(),
+            "link a -[knows]-> b points to missing key 'b'".to_string(),
+        ]);
+    }
+}
@@ -0,0 +1,115 @@
+//! Deferred maintenance of the tag and link indexes
+//!
+//! Writes never touch the secondary indexes; deleting a key does, dropping
+//! its tags and every link to or from it. Between `defer_indexing` and
+//! `rebuild_indexes` those drops are only recorded, then applied together.
+
+use crate::BurrowDB;
+use std::collections::HashSet;
+
+impl BurrowDB {
+    /// Stop cleaning up tags and links as keys are deleted
+    ///
+    /// Until `rebuild_indexes`, deleted keys keep their tags and links, so
+    /// `keys_with_tag`, `tags_of` and `find_linked_to` may return stale
+    /// results and `health_check` reports those entries as problems.
+    /// Tagging or linking a deleted key that has been stored again first
+    /// drops what it carried before, as an immediate delete would have.
+    pub fn defer_indexing(&mut self) {
+        self.deferred_index_drops.get_or_insert_with(HashSet::new);
+    }
+
+    /// Apply the cleanup deferred since `defer_indexing` and resume
+    /// immediate maintenance
+    ///
+    /// The indexes end up as if every delete had updated them at once.
+    /// Returns how many deleted keys were cleaned up.
+    pub fn rebuild_indexes(&mut self) -> usize {
+        let drops = self.deferred_index_drops.take().unwrap_or_default();
+        for key in &drops {
+            self.links.remove_key(key);
+            self.tags.remove_key(key);
+        }
+        drops.len()
+    }
+
+    /// Whether index cleanup is currently deferred
+    pub fn is_indexing_deferred(&self) -> bool {
+        self.deferred_index_drops.is_some()
+    }
+
+    /// Drop a deleted normalized key's tags and links, or record it for later
+    pub(crate) fn drop_from_indexes(&mut self, key: &str) {
+        match &mut self.deferred_index_drops {
+            Some(drops) => {
+                drops.insert(key.to_string());
+            }
+            None => {
+                self.links.remove_key(key);
+                self.tags.remove_key(key);
+            }
+        }
+    }
+
+    /// Apply a deferred drop for a normalized key before it is indexed anew
+    pub(crate) fn settle_index_drop(&mut self, key: &str) {
+        if let Some(drops) = &mut self.deferred_index_drops {
+            if drops.remove(key) {
+                self.links.remove_key(key);
+                self.tags.remove_key(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Tag and link a batch of keys, deleting and re-storing some of them
+    fn run_workload(db: &mut BurrowDB) {
+        for i in 0..20 {
+            db.put_raw(format!("k{}", i), b"v".to_vec()).unwrap();
+            db.tag(&format!("k{}", i), if i % 2 == 0 { "even" } else { "odd" }).unwrap();
+        }
+        for i in 1..20 {
+            db.link(&format!("k{}", i), "next_of", &format!("k{}", i - 1)).unwrap();
+        }
+        for i in (0..20).step_by(3) {
+            db.delete(&format!("k{}", i)).unwrap();
+        }
+        db.put_raw("k3".to_string(), b"again".to_vec()).unwrap();
+        db.tag("k3", "reborn").unwrap();
+    }
+
+    #[test]
+    fn test_deferred_rebuild_matches_incremental() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut incremental = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        let mut deferred = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100).unwrap();
+
+        run_workload(&mut incremental);
+        deferred.defer_indexing();
+        run_workload(&mut deferred);
+        assert!(deferred.keys_with_tag("even").contains(&"k0"));
+        let saved = temp_dir.path().join("saved.txt");
+        deferred.save_to_file(&saved).unwrap();
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("c").to_str().unwrap(), 100).unwrap();
+        restored.load_from_file(&saved).unwrap();
+        assert_eq!(restored.keys_with_tag("even"), incremental.keys_with_tag("even"));
+
+        assert_eq!(deferred.rebuild_indexes(), 6);
+        assert!(!deferred.is_indexing_deferred());
+
+        for tag in ["even", "odd", "reborn"] {
+            assert_eq!(deferred.keys_with_tag(tag), incremental.keys_with_tag(tag));
+        }
+        for i in 0..20 {
+            let key = format!("k{}", i);
+            assert_eq!(deferred.find_linked_to(&key), incremental.find_linked_to(&key));
+            assert_eq!(deferred.tags_of(&key), incremental.tags_of(&key));
+        }
+        assert!(deferred.health_check().is_healthy());
+    }
+}
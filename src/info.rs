@@ -0,0 +1,109 @@
+//! One report gathering the database's introspection methods
+//!
+//! `info` combines `stats`, `config`, `uptime`, `load_factor` and the key
+//! and index counts into `section:` blocks of indented `field: value`
+//! lines, so a single call shows everything an operator usually asks about.
+
+use crate::BurrowDB;
+use std::fmt::{Display, Write};
+
+impl BurrowDB {
+    /// A sectioned status report
+    ///
+    /// Sections are `server`, `keyspace`, `memory`, `stats`, `config` and
+    /// `features`, each a `section:` line followed by `  field: value`
+    /// lines. A count that can't be read from the cold tier is reported as
+    /// `unavailable (<error>)` rather than failing the whole report.
+    pub fn info(&self) -> String {
+        let stats = self.stats();
+        let config = self.config();
+        let limit = |bytes: Option<usize>, none: &str| match bytes {
+            Some(bytes) => bytes.to_string(),
+            None => none.to_string(),
+        };
+        let or_unavailable = |result: crate::Result<String>| {
+            result.unwrap_or_else(|e| format!("unavailable ({})", e))
+        };
+
+        let mut out = String::new();
+        section(&mut out, "server", &[
+            ("version", &crate::version()),
+            ("data_dir", &config.data_dir.display()),
+            ("uptime_secs", &self.uptime().as_secs()),
+        ]);
+        section(&mut out, "keyspace", &[
+            ("keys", &or_unavailable(self.keys().map(|keys| keys.len().to_string()))),
+            ("hot_keys", &stats.hot_blocks),
+            ("expiring_keys", &self.expires_at.len()),
+            ("tags", &self.tags.counts().len()),
+            ("links", &self.links.iter().count()),
+            ("sets", &self.sets.len()),
+        ]);
+        let load = match self.load_factor() {
+            Some(load) => format!("{:.1}%", load * 100.0),
+            None => "n/a".to_string(),
+        };
+        section(&mut out, "memory", &[
+            ("hot_bytes", &stats.total_hot_size),
+            ("hot_bytes_uncompressed", &stats.raw_hot_size),
+            ("compressed_blocks", &stats.compressed_blocks),
+            ("disk_bytes", &or_unavailable(self.cold_storage.total_size().map(|n| n.to_string()))),
+            ("hot_load", &load),
+        ]);
+        section(&mut out, "stats", &[
+            ("reads", &self.access_counts.values().sum::<u64>()),
+            ("keys_read", &self.access_counts.len()),
+        ]);
+        section(&mut out, "config", &[
+            ("max_hot_blocks", &config.max_hot_blocks),
+            ("max_value_size", &limit(config.max_value_size, "unlimited")),
+            ("max_key_size", &limit(config.max_key_size, "unlimited")),
+            ("compression_threshold", &limit(config.compression_threshold, "off")),
+            ("log_level", &config.log_level),
+        ]);
+        section(&mut out, "features", &[
+            ("exclusive", &config.exclusive),
+            ("case_insensitive_keys", &config.case_insensitive_keys),
+            ("overwrite_protection", &config.overwrite_protection),
+            ("insertion_order", &config.insertion_order),
+            ("validator", &self.validator.is_some()),
+            ("evict_hook", &self.evict_hook.is_some()),
+            ("audit_log", &self.audit.is_some()),
+            ("observers", &self.observers.len()),
+        ]);
+        out
+    }
+}
+
+/// Append a `name:` line and one indented line per field
+fn section(out: &mut String, name: &str, fields: &[(&str, &dyn Display)]) {
+    let _ = writeln!(out, "{}:", name);
+    for (field, value) in fields {
+        let _ = writeln!(out, "  {}: {}", field, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_info_reports_each_section() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 1).unwrap();
+        db.put_raw("a".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("b".to_string(), b"22".to_vec()).unwrap();
+        db.tag("a", "red").unwrap();
+        db.get("a").unwrap();
+
+        let info = db.info();
+        let sections: Vec<&str> = info.lines().filter(|line| !line.starts_with(' ')).collect();
+        assert_eq!(sections, ["server:", "keyspace:", "memory:", "stats:", "config:", "features:"]);
+        let expected = ["  keys: 2", "  hot_keys: 1", "  tags: 1", "  reads: 1", "  max_hot_blocks: 1"];
+        for line in expected {
+            assert!(info.lines().any(|l| l == line), "missing {:?} in\n{}", line, info);
+        }
+        assert!(info.contains(&format!("  version: {}\n", crate::version())));
+    }
+}
@@ -0,0 +1,105 @@
+//! Everything the database holds about one key, for debugging
+
+use crate::aggregate::value_type;
+use crate::error::Result;
+use crate::BurrowDB;
+use std::time::Duration;
+
+/// The full internal record for a key, as returned by `inspect`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordInfo {
+    /// Normalized key
+    pub key: String,
+    pub value: Vec<u8>,
+    /// Which of `VALUE_TYPES` the value looks like
+    pub value_type: &'static str,
+    /// Whether the document is in the hot tier (else only on disk)
+    pub hot: bool,
+    /// Whether the hot tier copy is compressed
+    pub compressed: bool,
+    /// When the value was last written or touched (Unix seconds)
+    pub updated_at: u64,
+    /// Reads counted for `top_keys`
+    pub reads: u64,
+    /// Time left before expiry, if the key has a TTL that hasn't run out
+    pub ttl: Option<Duration>,
+    /// Whether the key has expired but not yet been removed
+    pub expired: bool,
+    /// Tags on the key, sorted
+    pub tags: Vec<String>,
+    /// Links from this key as `(rel, to)`, sorted
+    pub links_out: Vec<(String, String)>,
+    /// Links to this key as `(from, rel)`, sorted
+    pub links_in: Vec<(String, String)>,
+}
+
+impl BurrowDB {
+    /// Gather every piece of state kept for `key`, or `None` if it isn't stored
+    ///
+    /// Unlike `get`, this neither counts as a read nor promotes the key,
+    /// and it reports a key that has expired but is still stored rather
+    /// than removing it.
+    pub fn inspect(&self, key: &str) -> Result<Option<RecordInfo>> {
+        let key = self.normalize_key(key).into_owned();
+        let Some(value) = self.peek(&key)? else {
+            return Ok(None);
+        };
+
+        let block = self.hot_data.get(&key);
+        let owned = |pairs: Vec<(&str, &str)>| -> Vec<(String, String)> {
+            pairs.into_iter().map(|(a, b)| (a.to_string(), b.to_string())).collect()
+        };
+
+        Ok(Some(RecordInfo {
+            value_type: value_type(&value),
+            hot: block.is_some(),
+            compressed: block.is_some_and(|block| block.is_compressed()),
+            updated_at: self.updated_at(&key)?.unwrap_or_default(),
+            reads: self.access_counts.get(&key).copied().unwrap_or(0),
+            ttl: self.ttl(&key),
+            expired: self.is_expired(&key),
+            tags: self.tags.tags_of(&key).into_iter().map(str::to_string).collect(),
+            links_out: owned(self.links.outgoing_of(&key)),
+            links_in: owned(self.links.incoming_of(&key)),
+            value,
+            key,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_inspect_reports_every_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.put_raw("order".to_string(), b"42".to_vec()).unwrap();
+        db.put_raw("user".to_string(), b"Alice".to_vec()).unwrap();
+        db.link("order", "placed_by", "user").unwrap();
+        db.tag("user", "vip").unwrap();
+        db.expire("user", Duration::from_secs(60));
+        db.get("user").unwrap();
+        db.demote("order").unwrap();
+
+        let user = db.inspect("user").unwrap().unwrap();
+        assert_eq!(user.value_type, "string");
+        assert!(user.hot && !user.expired);
+        assert_eq!(user.reads, 1);
+        assert!(user.ttl.is_some());
+        assert_eq!(user.tags, ["vip"]);
+        assert_eq!(user.links_in, [("order".to_string(), "placed_by".to_string())]);
+
+        let order = db.inspect("order").unwrap().unwrap();
+        assert!(!order.hot);
+        assert_eq!(order.value_type, "integer");
+        assert_eq!(order.links_out, [("placed_by".to_string(), "user".to_string())]);
+        assert!(order.updated_at > 0);
+
+        db.expire("order", Duration::ZERO);
+        assert!(db.inspect("order").unwrap().unwrap().expired);
+        assert_eq!(db.inspect("missing").unwrap(), None);
+    }
+}
@@ -0,0 +1,508 @@
+//! Minimal JSON support for exports and patches
+//!
+//! The core crate has no JSON dependency, so this module implements just
+//! enough of the format to write and read flat objects whose values are
+//! strings. Values are treated as UTF-8 text; any invalid byte sequences
+//! are replaced with U+FFFD when exported.
+//!
+//! `merge_json` also reads objects with arbitrary field values, but only
+//! checks their syntax and copies them in compact form; it never
+//! interprets them.
+
+use crate::error::{BurrowError, Result};
+use crate::BurrowDB;
+use std::io::{BufRead, Write};
+
+impl BurrowDB {
+    /// Stream every document as JSON Lines
+    ///
+    /// Each line is a `{"key":"...","value":"..."}` object. Cold documents
+    /// are read one at a time, so the whole database is never held in memory.
+    pub fn write_jsonl<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.for_each_entry(|key, value| {
+            let mut line = String::from("{\"key\":");
+            push_json_string(key, &mut line);
+            line.push_str(",\"value\":");
+            push_json_string(&String::from_utf8_lossy(value), &mut line);
+            line.push_str("}\n");
+
+            w.write_all(line.as_bytes())?;
+            Ok(())
+        })
+    }
+
+    /// Export the documents accepted by `pred` as one JSON object
+    ///
+    /// `pred` is called with each key and its value as text; the object's
+    /// fields are sorted by key so exports of the same data compare equal.
+    pub fn export_json_filtered<F: Fn(&str, &str) -> bool>(&self, pred: F) -> Result<String> {
+        let mut entries = Vec::new();
+        self.for_each_entry(|key, value| {
+            let value = String::from_utf8_lossy(value);
+            if pred(key, &value) {
+                entries.push((key.to_string(), value.into_owned()));
+            }
+            Ok(())
+        })?;
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        let mut out = String::from("{");
+        for (index, (key, value)) in entries.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            push_json_string(key, &mut out);
+            out.push(':');
+            push_json_string(value, &mut out);
+        }
+        out.push('}');
+        Ok(out)
+    }
+
+    /// Load documents from JSON Lines written by `write_jsonl`
+    ///
+    /// Blank lines are skipped. Returns the number of documents loaded.
+    pub fn read_jsonl<R: BufRead>(&mut self, r: R) -> Result<usize> {
+        let mut loaded = 0;
+
+        for entry in jsonl_entries(r) {
+            let (key, value) = entry?;
+            self.put_raw(key, value)?;
+            loaded += 1;
+        }
+
+        Ok(loaded)
+    }
+
+    /// Shallow-merge the JSON object `patch` into the object stored at `key`
+    ///
+    /// Each field of `patch` replaces the stored field of the same name, or
+    /// is appended if there is none; a `null` removes the field instead.
+    /// Nested objects are replaced whole, not merged. The result is written
+    /// back in compact form.
+    ///
+    /// The key keeps its TTL, and the read isn't counted as an access.
+    /// Returns `KeyNotFound` (also for an expired key), or `InvalidDocument`
+    /// if the stored value or the patch isn't a JSON object.
+    pub fn merge_json(&mut self, key: &str, patch: &str) -> Result<()> {
+        let key = self.normalize_key(key).into_owned();
+        if self.is_expired(&key) {
+            self.remove_expired(&key)?;
+            return Err(BurrowError::KeyNotFound(key));
+        }
+        let stored = self.peek(&key)?.ok_or_else(|| BurrowError::KeyNotFound(key.clone()))?;
+        let not_object = |what: &str, msg: &str| {
+            BurrowError::InvalidDocument(format!("{} is not a JSON object: {}", what, msg))
+        };
+
+        let stored = std::str::from_utf8(&stored)
+            .map_err(|_| not_object(&key, "value is not valid UTF-8"))?;
+        let mut fields = parse_object(stored).map_err(|msg| not_object(&key, &msg))?;
+        for (name, value) in parse_object(patch).map_err(|msg| not_object("patch", &msg))? {
+            let existing = fields.iter().position(|(field, _)| *field == name);
+            match (existing, value == "null") {
+                (Some(index), true) => {
+                    fields.remove(index);
+                }
+                (Some(index), false) => fields[index].1 = value,
+                (None, true) => {}
+                (None, false) => fields.push((name, value)),
+            }
+        }
+
+        let mut merged = String::from("{");
+        for (index, (name, value)) in fields.iter().enumerate() {
+            if index > 0 {
+                merged.push(',');
+            }
+            push_json_string(name, &mut merged);
+            merged.push(':');
+            merged.push_str(value);
+        }
+        merged.push('}');
+
+        // Patching keeps the value's expiry, which `store` would clear
+        let expiry = self.save_expiry(&key);
+        self.put_raw_force(key.clone(), merged.into_bytes())?;
+        self.restore_expiry(&key, expiry);
+        Ok(())
+    }
+
+    /// Like `read_jsonl`, but refuse to overwrite existing keys
+    ///
+    /// The whole input is parsed first. If any of its keys is already
+    /// stored, nothing is loaded and this fails with `KeysExist`.
+    pub fn read_jsonl_strict<R: BufRead>(&mut self, r: R) -> Result<usize> {
+        let entries = jsonl_entries(r).collect::<Result<Vec<_>>>()?;
+        self.reject_existing(&entries)?;
+        self.bulk_load(entries)
+    }
+}
+
+/// The records of a JSONL input, skipping blank lines
+fn jsonl_entries<R: BufRead>(r: R) -> impl Iterator<Item = Result<(String, Vec<u8>)>> {
+    r.lines().enumerate().filter_map(|(index, line)| {
+        let line = match line {
+            Ok(line) if line.trim().is_empty() => return None,
+            Ok(line) => line,
+            Err(e) => return Some(Err(e.into())),
+        };
+        Some(
+            parse_jsonl_record(&line)
+                .map(|(key, value)| (key, value.into_bytes()))
+                .map_err(|msg| {
+                    BurrowError::SerializationError(format!("line {}: {}", index + 1, msg))
+                }),
+        )
+    })
+}
+
+/// Extract the `key` and `value` fields from one JSONL record
+pub(crate) fn parse_jsonl_record(line: &str) -> std::result::Result<(String, String), String> {
+    let mut key = None;
+    let mut value = None;
+
+    for (field, text) in parse_string_object(line)? {
+        match field.as_str() {
+            "key" => key = Some(text),
+            "value" => value = Some(text),
+            _ => {}
+        }
+    }
+
+    match (key, value) {
+        (Some(key), Some(value)) => Ok((key, value)),
+        (None, _) => Err("missing \"key\" field".to_string()),
+        (_, None) => Err("missing \"value\" field".to_string()),
+    }
+}
+
+/// Append `text` to `out` as a quoted, escaped JSON string
+pub(crate) fn push_json_string(text: &str, out: &mut String) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Parse a JSON object whose values are all strings, keeping field order
+pub(crate) fn parse_string_object(text: &str) -> std::result::Result<Vec<(String, String)>, String> {
+    parse_object_with(text, Parser::string)
+}
+
+/// Parse a JSON object, keeping each field's value as compact JSON text
+fn parse_object(text: &str) -> std::result::Result<Vec<(String, String)>, String> {
+    parse_object_with(text, |parser| {
+        let mut value = String::new();
+        parser.value(&mut value)?;
+        Ok(value)
+    })
+}
+
+/// Parse a JSON object, reading each field's value with `value`
+fn parse_object_with<'a, T, F>(
+    text: &'a str,
+    mut value: F,
+) -> std::result::Result<Vec<(String, T)>, String>
+where
+    F: FnMut(&mut Parser<'a>) -> std::result::Result<T, String>,
+{
+    let mut parser = Parser { chars: text.chars().peekable() };
+    let mut fields = Vec::new();
+
+    parser.expect('{')?;
+    if !parser.consume('}') {
+        loop {
+            let field = parser.string()?;
+            parser.expect(':')?;
+            let value = value(&mut parser)?;
+            fields.push((field, value));
+
+            if parser.consume(',') {
+                continue;
+            }
+            parser.expect('}')?;
+            break;
+        }
+    }
+
+    parser.skip_whitespace();
+    if parser.chars.peek().is_some() {
+        return Err("unexpected trailing characters".to_string());
+    }
+
+    Ok(fields)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    /// Consume `expected` (after whitespace) if it is next
+    fn consume(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if_eq(&expected).is_some()
+    }
+
+    fn expect(&mut self, expected: char) -> std::result::Result<(), String> {
+        if self.consume(expected) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}'", expected))
+        }
+    }
+
+    fn string(&mut self) -> std::result::Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(out),
+                Some('\\') => match self.chars.next() {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('/') => out.push('/'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('u') => out.push(self.unicode_escape()?),
+                    _ => return Err("invalid escape sequence".to_string()),
+                },
+                Some(c) => out.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    /// Copy one JSON value to `out` in compact form, checking its syntax
+    fn value(&mut self, out: &mut String) -> std::result::Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('"') => {
+                let text = self.string()?;
+                push_json_string(&text, out);
+                Ok(())
+            }
+            Some('{') => self.sequence('{', '}', out, |parser, out| {
+                let field = parser.string()?;
+                push_json_string(&field, out);
+                parser.expect(':')?;
+                out.push(':');
+                parser.value(out)
+            }),
+            Some('[') => self.sequence('[', ']', out, Self::value),
+            _ => self.literal(out),
+        }
+    }
+
+    /// Copy `open`, comma-separated items read by `item`, then `close`
+    fn sequence<F>(
+        &mut self,
+        open: char,
+        close: char,
+        out: &mut String,
+        mut item: F,
+    ) -> std::result::Result<(), String>
+    where
+        F: FnMut(&mut Self, &mut String) -> std::result::Result<(), String>,
+    {
+        self.expect(open)?;
+        out.push(open);
+        if !self.consume(close) {
+            loop {
+                item(self, out)?;
+                if self.consume(',') {
+                    out.push(',');
+                    continue;
+                }
+                self.expect(close)?;
+                break;
+            }
+        }
+        out.push(close);
+        Ok(())
+    }
+
+    /// Copy a number, `true`, `false` or `null`
+    fn literal(&mut self, out: &mut String) -> std::result::Result<(), String> {
+        let mut word = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+        {
+            word.push(c);
+        }
+
+        let number = word.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+            && word.ends_with(|c: char| c.is_ascii_digit())
+            && word.parse::<f64>().is_ok();
+        if !number && !matches!(word.as_str(), "true" | "false" | "null") {
+            return Err(match word.is_empty() {
+                true => "expected a value".to_string(),
+                false => format!("invalid value '{}'", word),
+            });
+        }
+        out.push_str(&word);
+        Ok(())
+    }
+
+    /// Decode the digits of a `\uXXXX` escape, joining surrogate pairs
+    fn unicode_escape(&mut self) -> std::result::Result<char, String> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| "invalid \\u escape".to_string());
+        }
+
+        if self.chars.next() != Some('\\') || self.chars.next() != Some('u') {
+            return Err("unpaired surrogate in \\u escape".to_string());
+        }
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err("unpaired surrogate in \\u escape".to_string());
+        }
+
+        let combined = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+        char::from_u32(combined).ok_or_else(|| "invalid \\u escape".to_string())
+    }
+
+    fn hex4(&mut self) -> std::result::Result<u32, String> {
+        let digits: String = self.chars.by_ref().take(4).collect();
+        if digits.len() != 4 {
+            return Err("truncated \\u escape".to_string());
+        }
+        u32::from_str_radix(&digits, 16).map_err(|_| "invalid \\u escape".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_string_object() {
+        let fields = parse_string_object(r#" {"key" : "a\"b", "value":"é🦀\n"} "#).unwrap();
+        assert_eq!(fields, vec![
+            ("key".to_string(), "a\"b".to_string()),
+            ("value".to_string(), "é🦀\n".to_string()),
+        ]);
+
+        assert!(parse_string_object(r#"{"key": 1}"#).is_err());
+        assert!(parse_string_object(r#"{"key": "a"} x"#).is_err());
+    }
+
+    #[test]
+    fn test_merge_json() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        let doc = r#"{"name": "Alice", "age": 30, "tags": ["a", "b"], "address": {"city": "Oslo"}}"#;
+        db.put_raw("user".to_string(), doc.as_bytes().to_vec()).unwrap();
+
+        let patch = r#"{"age": 31, "tags": null, "address": {"zip": "0150"}, "vip": true}"#;
+        db.merge_json("user", patch).unwrap();
+        assert_eq!(
+            db.get("user").unwrap().unwrap(),
+            br#"{"name":"Alice","age":31,"address":{"zip":"0150"},"vip":true}"#
+        );
+
+        db.put_raw("list".to_string(), b"[1, 2]".to_vec()).unwrap();
+        assert!(matches!(db.merge_json("list", "{}"), Err(BurrowError::InvalidDocument(_))));
+        assert!(matches!(db.merge_json("user", "[]"), Err(BurrowError::InvalidDocument(_))));
+        assert!(matches!(
+            db.merge_json("user", r#"{"age": 3l}"#),
+            Err(BurrowError::InvalidDocument(_))
+        ));
+        assert!(matches!(db.merge_json("missing", "{}"), Err(BurrowError::KeyNotFound(_))));
+        assert!(db.get("user").unwrap().unwrap().starts_with(br#"{"name":"Alice","age":31"#));
+    }
+
+    #[test]
+    fn test_merge_json_keeps_the_ttl() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.put_raw("session".to_string(), br#"{"user": "alice"}"#.to_vec()).unwrap();
+        db.expire("session", Duration::from_secs(60));
+
+        db.merge_json("session", r#"{"seen": 2}"#).unwrap();
+        assert!(db.ttl("session").is_some_and(|ttl| ttl > Duration::from_secs(50)));
+        assert_eq!(db.peek("session").unwrap().unwrap(), br#"{"user":"alice","seen":2}"#);
+        assert_eq!(db.access_count("session"), 0);
+
+        db.put_raw("gone".to_string(), b"{}".to_vec()).unwrap();
+        db.expire("gone", Duration::ZERO);
+        assert!(matches!(db.merge_json("gone", "{}"), Err(BurrowError::KeyNotFound(_))));
+        assert!(db.peek("gone").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_jsonl_strict_refuses_overlap() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.put_raw("a".to_string(), b"mine".to_vec()).unwrap();
+        db.put_raw("c".to_string(), b"mine".to_vec()).unwrap();
+
+        let input = "{\"key\":\"c\",\"value\":\"1\"}\n{\"key\":\"b\",\"value\":\"2\"}\n\
+                     {\"key\":\"a\",\"value\":\"3\"}\n";
+        let err = db.read_jsonl_strict(input.as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "Keys already exist: a, c");
+        assert!(!db.contains_key("b"));
+        assert_eq!(db.get("a").unwrap().unwrap(), b"mine");
+
+        db.delete("a").unwrap();
+        db.delete("c").unwrap();
+        assert_eq!(db.read_jsonl_strict(input.as_bytes()).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_jsonl_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        db.put_raw("user:1".to_string(), br#"{"name": "Alice"}"#.to_vec()).unwrap();
+        db.put_raw("note".to_string(), b"line one\nline two".to_vec()).unwrap();
+
+        let mut out = Vec::new();
+        db.write_jsonl(&mut out).unwrap();
+        assert_eq!(out.iter().filter(|&&b| b == b'\n').count(), 2);
+
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100).unwrap();
+        assert_eq!(restored.read_jsonl(&out[..]).unwrap(), 2);
+        assert_eq!(restored.get("user:1").unwrap().unwrap(), br#"{"name": "Alice"}"#);
+        assert_eq!(restored.get("note").unwrap().unwrap(), b"line one\nline two");
+    }
+
+    #[test]
+    fn test_export_json_filtered() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.put_raw("user:2".to_string(), b"Bob".to_vec()).unwrap();
+        db.put_raw("user:1".to_string(), b"say \"hi\"".to_vec()).unwrap();
+        db.put_raw("order:1".to_string(), b"pending".to_vec()).unwrap();
+
+        let json = db.export_json_filtered(|key, _| key.starts_with("user:")).unwrap();
+        assert_eq!(json, r#"{"user:1":"say \"hi\"","user:2":"Bob"}"#);
+        let fields = parse_string_object(&json).unwrap();
+        assert_eq!(fields.len(), 2);
+
+        let json = db.export_json_filtered(|_, value| value == "pending").unwrap();
+        assert_eq!(json, r#"{"order:1":"pending"}"#);
+        assert_eq!(db.export_json_filtered(|_, _| false).unwrap(), "{}");
+    }
+}
@@ -3,25 +3,127 @@
 //! This is the core database engine that handles pure FlatBuffer documents.
 //! For JSON support, use the `burrow_client` crate.
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
 
+pub mod audit;
+pub mod binary_format;
+pub mod builder;
+pub mod cli;
+pub mod csv_format;
+pub mod diff;
 pub mod document_block;
+pub mod dot_format;
+pub mod encrypted_format;
 pub mod error;
+pub mod events;
+pub mod health;
+pub mod inspect;
+pub mod json_format;
+pub mod logging;
+pub mod merge;
+pub mod roundtrip;
+pub mod shared;
+pub mod sharded;
 pub mod storage;
+pub mod text_format;
+pub mod transaction;
+pub mod transforms;
+pub mod validation;
 
+mod aggregate;
+mod checksum;
+mod compression;
 mod generated;
+mod glob;
+mod indexing;
+mod info;
+mod links;
+mod lock;
+mod rng;
+mod sample;
+mod sets;
+mod settings;
+mod tags;
+mod tree;
+mod ttl;
 
+pub use aggregate::{Reducer, VALUE_TYPES};
+pub use audit::{AuditEntry, AuditOp};
+pub use builder::BurrowDBBuilder;
+pub use diff::Diff;
 pub use document_block::DocumentBlock;
+pub use dot_format::DotFormat;
 pub use error::{BurrowError, Result};
+pub use events::{Event, EvictHook, Observer};
+pub use health::HealthReport;
+pub use inspect::RecordInfo;
+pub use logging::LogLevel;
+pub use merge::{MergeStrategy, MergeSummary};
+pub use shared::SharedBurrowDB;
+pub use sharded::ShardedBurrowDB;
 pub use storage::Storage;
+pub use text_format::TextFormat;
+pub use transaction::Transaction;
+pub use transforms::Transform;
+pub use validation::Validator;
+
+use audit::AuditLog;
+use links::LinkIndex;
+use lock::DirLock;
+use tags::TagIndex;
+
+/// Value size above which `with_compression` compresses hot tier values
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+
+/// How many conflicting keys a strict import names in `KeysExist`
+pub const MAX_REPORTED_CONFLICTS: usize = 10;
+
+/// The version of this crate, as in its `Cargo.toml`
+pub fn version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
 
 /// Database statistics
 #[derive(Debug, Clone)]
 pub struct DatabaseStats {
     /// Number of documents in hot tier (RAM)
     pub hot_blocks: usize,
-    /// Total size of hot tier in bytes
+    /// Total size of hot tier in bytes, as held in memory
     pub total_hot_size: usize,
+    /// Total size of hot tier documents before compression
+    pub raw_hot_size: usize,
+    /// Number of hot tier documents held compressed
+    pub compressed_blocks: usize,
+}
+
+/// Settings a database was opened with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Directory for cold tier storage
+    pub data_dir: PathBuf,
+    /// Maximum documents in hot tier before eviction
+    pub max_hot_blocks: usize,
+    /// Whether this database holds the data directory's lock
+    pub exclusive: bool,
+    /// Hot tier values longer than this are compressed (`None` = never)
+    pub compression_threshold: Option<usize>,
+    /// Whether keys are normalized to lowercase
+    pub case_insensitive_keys: bool,
+    /// Whether `put` and `put_raw` refuse to overwrite existing keys
+    pub overwrite_protection: bool,
+    /// Largest accepted value in bytes (`None` = unlimited)
+    pub max_value_size: Option<usize>,
+    /// Largest accepted key in bytes (`None` = unlimited)
+    pub max_key_size: Option<usize>,
+    /// Which operations are logged to stderr
+    pub log_level: LogLevel,
+    /// Whether keys are listed in insertion order
+    pub insertion_order: bool,
 }
 
 /// BurrowDB - Block-based document database with hot-cold tiering
@@ -36,6 +138,49 @@ pub struct BurrowDB {
     cold_storage: Storage,
     /// Maximum number of blocks in hot tier before eviction
     max_hot_blocks: usize,
+    /// Relationship links between keys
+    links: LinkIndex,
+    /// Tags attached to keys
+    tags: TagIndex,
+    /// Whether keys are normalized to lowercase
+    case_insensitive_keys: bool,
+    /// Successful reads per key (kept across overwrites, cleared on delete)
+    access_counts: HashMap<String, u64>,
+    /// Largest accepted value in bytes (`None` = unlimited)
+    max_value_size: Option<usize>,
+    /// Largest accepted key in bytes (`None` = unlimited)
+    max_key_size: Option<usize>,
+    /// Set values by key, separate from documents
+    sets: HashMap<String, HashSet<String>>,
+    /// Which operations are logged to stderr
+    log_level: LogLevel,
+    /// When keys with a TTL expire
+    expires_at: HashMap<String, SystemTime>,
+    /// How long each TTL given by `expire` ran, so `touch` can restart it
+    ttl_lengths: HashMap<String, Duration>,
+    /// Read-time transforms by name
+    transforms: HashMap<String, Transform>,
+    /// Callbacks notified of events
+    observers: Vec<Observer>,
+    /// Callback run on each document LRU eviction pushes out of the hot tier
+    evict_hook: Option<EvictHook>,
+    /// Keys in the order they were first stored, when that mode is enabled
+    insertion_order: Option<Vec<String>>,
+    /// Hot tier values longer than this are held compressed
+    compression_threshold: Option<usize>,
+    /// Whether `put` and `put_raw` refuse to overwrite existing keys
+    overwrite_protection: bool,
+    /// Custom check run before every write
+    validator: Option<Validator>,
+    /// Held while open with `BurrowDBBuilder::exclusive`
+    lock: Option<DirLock>,
+    /// Deleted keys whose tags and links are still to be dropped, while
+    /// `defer_indexing` is in effect
+    deferred_index_drops: Option<HashSet<String>>,
+    /// When this database was opened, for `uptime`
+    started_at: Instant,
+    /// Recent operations, when `set_audit_log` has turned it on
+    audit: Option<AuditLog>,
 }
 
 impl BurrowDB {
@@ -43,7 +188,12 @@ impl BurrowDB {
     ///
     /// Uses "./data" as the data directory and 1000 max hot blocks.
     pub fn new() -> Result<Self> {
-        Self::with_config("./data", 1000)
+        Self::builder().build()
+    }
+
+    /// Start configuring a BurrowDB with the builder
+    pub fn builder() -> BurrowDBBuilder {
+        BurrowDBBuilder::default()
     }
 
     /// Create a new BurrowDB with custom configuration
@@ -52,11 +202,76 @@ impl BurrowDB {
     /// * `data_dir` - Directory for cold tier storage
     /// * `max_hot_blocks` - Maximum documents in hot tier before eviction
     pub fn with_config(data_dir: &str, max_hot_blocks: usize) -> Result<Self> {
-        Ok(Self {
-            hot_data: HashMap::new(),
-            cold_storage: Storage::new(data_dir)?,
-            max_hot_blocks,
-        })
+        Self::builder()
+            .data_dir(data_dir)
+            .max_entries(max_hot_blocks)
+            .build()
+    }
+
+    /// Normalize keys to lowercase on every operation
+    ///
+    /// Keys that differ only in case then refer to the same document and
+    /// `keys()` returns the lowercase forms. Existing mixed-case keys would
+    /// become unreachable (or collide with their lowercase twin), so this is
+    /// rejected with `InvalidConfig` if any stored key is not already lowercase.
+    pub fn with_case_insensitive_keys(mut self) -> Result<Self> {
+        if let Some(key) = self.keys()?.into_iter().find(|k| *k != k.to_lowercase()) {
+            return Err(BurrowError::InvalidConfig(format!(
+                "cannot enable case-insensitive keys: existing key '{}' is not lowercase",
+                key
+            )));
+        }
+
+        self.case_insensitive_keys = true;
+        Ok(self)
+    }
+
+    /// Reject values larger than `bytes` with `ValueTooLarge`
+    ///
+    /// By default values have no size limit.
+    pub fn with_max_value_size(mut self, bytes: usize) -> Self {
+        self.max_value_size = Some(bytes);
+        self
+    }
+
+    /// Compress hot tier values larger than `DEFAULT_COMPRESSION_THRESHOLD`
+    ///
+    /// See `with_compression_threshold`.
+    pub fn with_compression(self) -> Self {
+        self.with_compression_threshold(DEFAULT_COMPRESSION_THRESHOLD)
+    }
+
+    /// Compress hot tier values larger than `bytes`
+    ///
+    /// Compression is transparent: reads return the exact original bytes,
+    /// and smaller values, values that don't shrink, and cold tier files are
+    /// stored as-is. Size limits apply to the original value. Values already
+    /// in the hot tier are compressed the next time they are written.
+    pub fn with_compression_threshold(mut self, bytes: usize) -> Self {
+        self.compression_threshold = Some(bytes);
+        self
+    }
+
+    /// Reject keys larger than `bytes` with `KeyTooLarge`
+    ///
+    /// By default keys have no size limit.
+    pub fn with_max_key_size(mut self, bytes: usize) -> Self {
+        self.max_key_size = Some(bytes);
+        self
+    }
+
+    /// List keys in the order they were first stored
+    ///
+    /// `keys()` and every scan over documents (exports, JSON Lines) then
+    /// follow insertion order instead of hash order. Overwriting a key keeps
+    /// its position; deleting it and storing it again moves it to the end.
+    /// Keys already present are ordered by name. Lookups are unaffected, but
+    /// deletes scan the order list, so they become O(n).
+    pub fn with_insertion_order(mut self) -> Result<Self> {
+        let mut existing = self.keys()?;
+        existing.sort_unstable();
+        self.insertion_order = Some(existing);
+        Ok(self)
     }
 
     /// Store a FlatBuffer document
@@ -64,15 +279,9 @@ impl BurrowDB {
     /// The document is stored in the hot tier. If the hot tier exceeds
     /// `max_hot_blocks`, LRU eviction moves older documents to cold tier.
     pub fn put(&mut self, key: String, flatbuffer_bytes: Vec<u8>) -> Result<()> {
+        self.check_overwrite(&key)?;
         let block = DocumentBlock::new(flatbuffer_bytes)?;
-        self.hot_data.insert(key, block);
-
-        // Check if eviction is needed
-        if self.hot_data.len() > self.max_hot_blocks {
-            self.evict_lru()?;
-        }
-
-        Ok(())
+        self.store(key, block)
     }
 
     /// Store raw bytes (no FlatBuffer validation)
@@ -80,8 +289,118 @@ impl BurrowDB {
     /// This is a low-level API for the server layer. Clients are responsible
     /// for ensuring data validity. Use `put()` for FlatBuffer documents.
     pub fn put_raw(&mut self, key: String, data: Vec<u8>) -> Result<()> {
+        self.check_overwrite(&key)?;
+        self.put_raw_force(key, data)
+    }
+
+    /// Store raw bytes, overwriting an existing key even with overwrite
+    /// protection on
+    pub fn put_raw_force(&mut self, key: String, data: Vec<u8>) -> Result<()> {
         let block = DocumentBlock::from_raw(data);
-        self.hot_data.insert(key, block);
+        self.store(key, block)
+    }
+
+    /// Reject `put` and `put_raw` on existing keys with `KeyExists`
+    ///
+    /// With protection on, overwriting takes an explicit `put_raw_force`,
+    /// `replace` or `modify`. Loading a save file goes through `put_raw`, so
+    /// it also refuses to overwrite. Off by default.
+    pub fn with_overwrite_protection(mut self, enabled: bool) -> Self {
+        self.overwrite_protection = enabled;
+        self
+    }
+
+    /// Fail with `KeyExists` if overwrite protection forbids storing `key`
+    fn check_overwrite(&self, key: &str) -> Result<()> {
+        if self.overwrite_protection && self.contains_key(key) {
+            return Err(BurrowError::KeyExists(self.normalize_key(key).into_owned()));
+        }
+        Ok(())
+    }
+
+    /// Make room in the hot tier for `additional` more documents up front
+    ///
+    /// Saves rehashing the hot tier piecemeal during a large import. Never
+    /// reserves past `max_entries`, since the hot tier evicts rather than
+    /// growing beyond it; returns how many documents room was made for.
+    pub fn reserve(&mut self, additional: usize) -> usize {
+        let room = self.max_hot_blocks.saturating_sub(self.hot_data.len());
+        let additional = additional.min(room);
+        self.hot_data.reserve(additional);
+        additional
+    }
+
+    /// Store many raw values at once, returning how many were stored
+    ///
+    /// The end state matches calling `put_raw` for each entry in order, but
+    /// the hot tier is reserved up front and, once it is full, remaining
+    /// entries are written straight to the cold tier instead of repeatedly
+    /// triggering LRU eviction. There are no value indexes to rebuild; links
+    /// and access counts are keyed by name and carry over as with `put_raw`.
+    ///
+    /// Stops at the first entry that fails a size limit, is refused by
+    /// overwrite protection or can't be written; entries before it stay
+    /// stored.
+    pub fn bulk_load<I>(&mut self, entries: I) -> Result<usize>
+    where
+        I: IntoIterator<Item = (String, Vec<u8>)>,
+    {
+        let entries = entries.into_iter();
+        self.reserve(entries.size_hint().0);
+
+        let mut loaded = 0;
+        for (key, value) in entries {
+            let key = self.normalize_owned_key(key);
+            self.check_overwrite(&key)?;
+            self.check_write(&key, &value)?;
+            let block = DocumentBlock::from_raw(value);
+            self.clear_expiry(&key);
+            self.record_insertion(&key);
+
+            if self.hot_data.len() < self.max_hot_blocks || self.hot_data.contains_key(&key) {
+                let block = self.compressed_for_hot(block);
+                self.hot_data.insert(key.clone(), block);
+            } else {
+                self.cold_storage.save(&key, &block)?;
+            }
+            self.emit_put(&key);
+            loaded += 1;
+        }
+
+        Ok(loaded)
+    }
+
+    /// Fail with `KeysExist` if any of `entries`' keys is already stored
+    pub(crate) fn reject_existing(&self, entries: &[(String, Vec<u8>)]) -> Result<()> {
+        let mut existing: Vec<String> = entries
+            .iter()
+            .filter(|(key, _)| self.contains_key(key))
+            .map(|(key, _)| self.normalize_key(key).into_owned())
+            .collect();
+        if existing.is_empty() {
+            return Ok(());
+        }
+
+        existing.sort_unstable();
+        existing.dedup();
+        let total = existing.len();
+        existing.truncate(MAX_REPORTED_CONFLICTS);
+        Err(BurrowError::KeysExist { keys: existing, total })
+    }
+
+    /// Insert a block into the hot tier, evicting if it overflows
+    fn store(&mut self, key: String, block: DocumentBlock) -> Result<()> {
+        let key = self.normalize_owned_key(key);
+        if let Err(e) = self.check_write(&key, block.as_bytes()) {
+            self.log(LogLevel::Debug, "put", &key, &format_args!("error ({})", e));
+            return Err(e);
+        }
+        self.log(LogLevel::Debug, "put", &key, &"ok");
+        self.clear_expiry(&key);
+        self.record_insertion(&key);
+        let block = self.compressed_for_hot(block);
+        self.hot_data.insert(key.clone(), block);
+        self.emit_put(&key);
 
         // Check if eviction is needed
         if self.hot_data.len() > self.max_hot_blocks {
@@ -91,24 +410,115 @@ impl BurrowDB {
         Ok(())
     }
 
+    /// Overwrite an existing document, returning its previous value
+    ///
+    /// Unlike `put_raw`, this never creates a document: a missing or
+    /// expired key is reported as `KeyNotFound` and nothing is stored.
+    pub fn replace(&mut self, key: &str, value: Vec<u8>) -> Result<Vec<u8>> {
+        let key = self.normalize_key(key).into_owned();
+        if self.is_expired(&key) {
+            self.remove_expired(&key)?;
+            return Err(BurrowError::KeyNotFound(key));
+        }
+        let old = self
+            .peek(&key)?
+            .ok_or_else(|| BurrowError::KeyNotFound(key.clone()))?;
+        self.put_raw_force(key, value)?;
+        Ok(old)
+    }
+
+    /// Store a copy of the document at `from` under `to`
+    ///
+    /// `from` is left as it is; links, tags and expiry are not copied. A
+    /// missing or expired `from` is `KeyNotFound`. An existing `to` is
+    /// `KeyExists` unless `overwrite` is set, which like `replace` also
+    /// passes overwrite protection.
+    pub fn copy(&mut self, from: &str, to: &str, overwrite: bool) -> Result<()> {
+        let from = self.normalize_key(from).into_owned();
+        if self.is_expired(&from) {
+            self.remove_expired(&from)?;
+            return Err(BurrowError::KeyNotFound(from));
+        }
+        let value = self
+            .peek(&from)?
+            .ok_or_else(|| BurrowError::KeyNotFound(from.clone()))?;
+        if !overwrite && self.contains_key(to) {
+            return Err(BurrowError::KeyExists(self.normalize_key(to).into_owned()));
+        }
+        self.put_raw_force(to.to_string(), value)
+    }
+
+    /// Store `value` at `key` and return the value it replaced
+    ///
+    /// `None` if the key was absent or expired, in which case it is created.
+    /// Like `replace`, this passes overwrite protection.
+    pub fn getset(&mut self, key: &str, value: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        let key = self.normalize_key(key).into_owned();
+        let old = if self.is_expired(&key) { None } else { self.peek(&key)? };
+        self.put_raw_force(key, value)?;
+        Ok(old)
+    }
+
+    /// Add `by` to the integer at `key` and return the new total
+    ///
+    /// A missing or expired key counts as zero. The value is read as
+    /// `find_int_in_range` reads it; anything else, or a total outside the
+    /// `i64` range, is `InvalidDocument` and leaves the value as it was.
+    /// Storing the total clears any expiry, like any other write.
+    pub fn incr(&mut self, key: &str, by: i64) -> Result<i64> {
+        let key = self.normalize_key(key).into_owned();
+        let current = match self.peek(&key)? {
+            Some(_) if self.is_expired(&key) => 0,
+            Some(value) => aggregate::parse_int(&value).ok_or_else(|| {
+                BurrowError::InvalidDocument(format!("value of {} is not an integer", key))
+            })?,
+            None => 0,
+        };
+        let total = current.checked_add(by).ok_or_else(|| {
+            BurrowError::InvalidDocument(format!("incrementing {} overflows", key))
+        })?;
+
+        self.put_raw_force(key, total.to_string().into_bytes())?;
+        Ok(total)
+    }
+
     /// Retrieve a FlatBuffer document
     ///
     /// Checks hot tier first, then cold tier. Documents retrieved from
     /// cold tier are promoted to hot tier if there's room.
     pub fn get(&mut self, key: &str) -> Result<Option<Vec<u8>>> {
+        let key = &*self.normalize_key(key);
+        let result = self.fetch(key);
+        self.audit(AuditOp::Get, key);
+        self.log_outcome("get", key, &result, |value| {
+            if value.is_some() { "found" } else { "missing" }
+        });
+        result
+    }
+
+    /// Look up a normalized key, counting the read and promoting as `get` does
+    fn fetch(&mut self, key: &str) -> Result<Option<Vec<u8>>> {
+        if self.is_expired(key) {
+            self.remove_expired(key)?;
+            return Ok(None);
+        }
+
         // Check hot tier first
         if let Some(block) = self.hot_data.get_mut(key) {
             block.record_access();
-            return Ok(Some(block.as_bytes().to_vec()));
+            *self.access_counts.entry(key.to_string()).or_insert(0) += 1;
+            return Ok(Some(block.value().into_owned()));
         }
 
         // Check cold tier
         if self.cold_storage.exists(key) {
             let block = self.cold_storage.load(key)?;
+            *self.access_counts.entry(key.to_string()).or_insert(0) += 1;
             let bytes = block.as_bytes().to_vec();
 
             // Promote to hot tier if there's room
             if self.hot_data.len() < self.max_hot_blocks {
+                let block = self.compressed_for_hot(block);
                 self.hot_data.insert(key.to_string(), block);
             }
 
@@ -118,21 +528,212 @@ impl BurrowDB {
         Ok(None)
     }
 
+    /// Retrieve several documents at once, in the order requested
+    ///
+    /// Each key behaves as a `get`, so cold documents are promoted and reads
+    /// are counted. Missing keys yield `None`.
+    pub fn get_many(&mut self, keys: &[&str]) -> Result<Vec<Option<Vec<u8>>>> {
+        keys.iter().map(|key| self.get(key)).collect()
+    }
+
+    /// Read a document without promoting it or counting the access
+    pub(crate) fn peek(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let key = &*self.normalize_key(key);
+
+        if let Some(block) = self.hot_data.get(key) {
+            return Ok(Some(block.value().into_owned()));
+        }
+
+        if self.cold_storage.exists(key) {
+            return Ok(Some(self.cold_storage.load(key)?.as_bytes().to_vec()));
+        }
+
+        Ok(None)
+    }
+
+    /// Modify a document's bytes in place with `f`
+    ///
+    /// Hot documents are changed without copying the value out and back.
+    /// Cold documents are loaded, modified and placed in the hot tier.
+    /// Returns `false` (without calling `f`) if the key doesn't exist.
+    ///
+    /// With a value size limit or validator set, the original value is kept
+    /// aside so it can be restored if the modified value is rejected.
+    pub fn modify<F: FnOnce(&mut Vec<u8>)>(&mut self, key: &str, f: F) -> Result<bool> {
+        let key = &*self.normalize_key(key);
+        if self.is_expired(key) {
+            self.remove_expired(key)?;
+            return Ok(false);
+        }
+
+        let checked = self.max_value_size.is_some() || self.validator.is_some();
+        if let Some(block) = self.hot_data.get_mut(key) {
+            let original = checked.then(|| block.value().into_owned());
+            f(block.data_mut());
+
+            if let Some(original) = original {
+                if let Err(e) = self.check_write(key, self.hot_data[key].as_bytes()) {
+                    if let Some(block) = self.hot_data.get_mut(key) {
+                        *block.data_mut() = original;
+                    }
+                    return Err(e);
+                }
+            }
+
+            if let Some(block) = self.hot_data.get_mut(key) {
+                if let Some(threshold) = self.compression_threshold {
+                    block.compress_above(threshold);
+                }
+                block.touch();
+            }
+            self.emit_put(key);
+            return Ok(true);
+        }
+
+        if self.cold_storage.exists(key) {
+            let mut block = self.cold_storage.load(key)?;
+            f(block.data_mut());
+            block.touch();
+
+            // Modifying keeps the value's expiry, which `store` would clear
+            let expiry = self.save_expiry(key);
+            self.store(key.to_string(), block)?;
+            self.restore_expiry(key, expiry);
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
     /// Delete a document from both tiers
+    ///
+    /// Any links from or to the document are removed as well. Observers
+    /// are sent `Event::Deleted` only if the key existed.
     pub fn delete(&mut self, key: &str) -> Result<()> {
+        let key = &*self.normalize_key(key);
+        if self.discard(key)? {
+            self.audit(AuditOp::Delete, key);
+            self.emit(Event::Deleted { key: key.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Remove a normalized key from both tiers and every index without
+    /// announcing it, returning whether it existed
+    pub(crate) fn discard(&mut self, key: &str) -> Result<bool> {
         // Remove from hot tier
-        self.hot_data.remove(key);
+        let was_hot = self.hot_data.remove(key).is_some();
+        self.drop_from_indexes(key);
+        self.access_counts.remove(key);
+        self.clear_expiry(key);
+        if let Some(order) = &mut self.insertion_order {
+            order.retain(|k| k != key);
+        }
 
         // Remove from cold tier if exists
-        if self.cold_storage.exists(key) {
-            self.cold_storage.delete(key)?;
+        let result = if self.cold_storage.exists(key) {
+            self.cold_storage.delete(key).map(|()| true)
+        } else {
+            Ok(was_hot)
+        };
+
+        self.log_outcome("delete", key, &result, |_| "ok");
+        result
+    }
+
+    /// Mark a document as updated without changing its value
+    ///
+    /// Cold-tier documents stay on disk; only their timestamp is bumped.
+    /// A TTL set by `expire` starts over at its full length. A key that has
+    /// already expired is removed rather than revived. Returns whether the
+    /// key existed.
+    pub fn touch(&mut self, key: &str) -> Result<bool> {
+        let key = &*self.normalize_key(key);
+        if self.is_expired(key) {
+            self.remove_expired(key)?;
+            return Ok(false);
+        }
+
+        if let Some(block) = self.hot_data.get_mut(key) {
+            block.touch();
+        } else if self.cold_storage.exists(key) {
+            self.cold_storage.touch(key)?;
+        } else {
+            return Ok(false);
+        }
+
+        self.restart_ttl(key);
+        Ok(true)
+    }
+
+    /// Number of successful `get` calls for a key since it was last deleted
+    pub fn access_count(&self, key: &str) -> u64 {
+        self.access_counts
+            .get(&*self.normalize_key(key))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// The `n` most-read keys, busiest first
+    pub fn top_keys(&self, n: usize) -> Vec<(&str, u64)> {
+        let mut counts: Vec<(&str, u64)> = self.access_counts
+            .iter()
+            .map(|(key, count)| (key.as_str(), *count))
+            .collect();
+
+        counts.sort_by(|(a_key, a_count), (b_key, b_count)| {
+            b_count.cmp(a_count).then_with(|| a_key.cmp(b_key))
+        });
+        counts.truncate(n);
+        counts
+    }
+
+    /// Check whether a live document exists in either tier
+    ///
+    /// A key past its expiry counts as gone, as it does for `get`, even
+    /// before it has been removed. Removal still waits for the next `get`
+    /// or `flush_expired`, since checking doesn't take `&mut self`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        let key = &*self.normalize_key(key);
+        !self.is_expired(key) && self.is_stored(key)
+    }
+
+    /// Whether a normalized key is in either tier, expired or not
+    fn is_stored(&self, key: &str) -> bool {
+        self.hot_data.contains_key(key) || self.cold_storage.exists(key)
+    }
+
+    /// Link `from` to `to` with a named relationship
+    ///
+    /// Both documents must exist. Linking the same pair with the same
+    /// relationship twice has no effect.
+    pub fn link(&mut self, from: &str, rel: &str, to: &str) -> Result<()> {
+        let from = &*self.normalize_key(from).into_owned();
+        let to = &*self.normalize_key(to).into_owned();
+
+        for key in [from, to] {
+            if !self.contains_key(key) {
+                return Err(BurrowError::KeyNotFound(key.to_string()));
+            }
         }
 
+        self.settle_index_drop(from);
+        self.settle_index_drop(to);
+        self.links.add(from, rel, to);
         Ok(())
     }
 
+    /// Keys that link to `target` through any relationship, sorted
+    pub fn find_linked_to(&self, target: &str) -> Vec<&str> {
+        self.links.linked_to(&self.normalize_key(target))
+    }
+
     /// List all document keys (from both tiers)
     pub fn keys(&self) -> Result<Vec<String>> {
+        if let Some(order) = &self.insertion_order {
+            return Ok(order.clone());
+        }
+
         let mut all_keys: Vec<String> = self.hot_data.keys().cloned().collect();
 
         // Add cold tier keys (avoiding duplicates)
@@ -146,8 +747,317 @@ impl BurrowDB {
         Ok(all_keys)
     }
 
+    /// The lexicographically smallest key, if any
+    pub fn first_key(&self) -> Result<Option<String>> {
+        Ok(self.keys()?.into_iter().min())
+    }
+
+    /// The lexicographically largest key, if any
+    pub fn last_key(&self) -> Result<Option<String>> {
+        Ok(self.keys()?.into_iter().max())
+    }
+
+    /// Page through keys in sorted order, like Redis `SCAN`
+    ///
+    /// Start with cursor 0 and pass each returned cursor back in; a returned
+    /// cursor of 0 means the scan is complete. The cursor is a position in the
+    /// sorted key list, so keys added or removed between calls can shift the
+    /// page boundaries. `count` is raised to at least 1.
+    pub fn scan_cursor(&self, cursor: usize, count: usize) -> Result<(Vec<String>, usize)> {
+        let mut keys = self.keys()?;
+        keys.sort_unstable();
+
+        let end = cursor.saturating_add(count.max(1));
+        let next = if end < keys.len() { end } else { 0 };
+        let page = keys
+            .into_iter()
+            .skip(cursor)
+            .take(end - cursor)
+            .collect();
+
+        Ok((page, next))
+    }
+
+    /// Keys matching a glob pattern (`*`, `?`, `[a-z]`, `\` escapes), sorted
+    pub fn keys_matching(&self, pattern: &str) -> Result<Vec<String>> {
+        let mut keys: Vec<String> = self
+            .keys()?
+            .into_iter()
+            .filter(|key| glob::glob_match(pattern, key))
+            .collect();
+        keys.sort_unstable();
+        Ok(keys)
+    }
+
+    /// Delete every key matching a glob pattern, returning how many
+    ///
+    /// Uses the same matching as `keys_matching`, so that can be called first
+    /// to preview what would be removed.
+    pub fn delete_matching(&mut self, pattern: &str) -> Result<usize> {
+        let keys = self.keys_matching(pattern)?;
+        for key in &keys {
+            self.delete(key)?;
+        }
+        Ok(keys.len())
+    }
+
+    /// Rename every key starting with `old_prefix` to start with `new_prefix`
+    ///
+    /// Returns how many keys were renamed. If any new name is already taken
+    /// by a key that isn't itself being renamed, this fails with `KeyExists`
+    /// and nothing changes. Expiry times and access counts move with their
+    /// keys; links and tags of renamed keys are dropped, as with `delete`.
+    pub fn rename_prefix(&mut self, old_prefix: &str, new_prefix: &str) -> Result<usize> {
+        let old_prefix = &*self.normalize_key(old_prefix);
+        let new_prefix = self.normalize_key(new_prefix).into_owned();
+
+        let keys = self.keys()?;
+        let renames: Vec<(String, String)> = keys
+            .iter()
+            .filter_map(|key| {
+                let rest = key.strip_prefix(old_prefix)?;
+                Some((key.clone(), format!("{}{}", new_prefix, rest)))
+            })
+            .collect();
+
+        let moving: HashSet<&str> = renames.iter().map(|(old, _)| old.as_str()).collect();
+        for (_, new) in &renames {
+            if !moving.contains(new.as_str()) && keys.contains(new) {
+                return Err(BurrowError::KeyExists(new.clone()));
+            }
+        }
+        for (_, new) in &renames {
+            self.check_sizes(new, &[])?;
+        }
+
+        // Read everything before deleting anything, since a new name may be
+        // another renamed key's old name
+        let mut moved = Vec::with_capacity(renames.len());
+        for (old, new) in renames {
+            let value = self.peek(&old)?.unwrap_or_default();
+            let expiry = self.save_expiry(&old);
+            let reads = self.access_counts.get(&old).copied();
+            moved.push((old, new, value, expiry, reads));
+        }
+        for (_, new, value, ..) in &moved {
+            self.check_write(new, value)?;
+        }
+
+        for (old, ..) in &moved {
+            self.delete(old)?;
+        }
+
+        let count = moved.len();
+        for (_, new, value, expiry, reads) in moved {
+            self.store(new.clone(), DocumentBlock::from_raw(value))?;
+            self.restore_expiry(&new, expiry);
+            if let Some(reads) = reads {
+                self.access_counts.insert(new, reads);
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Count keys grouped by their prefix before the first `separator`
+    ///
+    /// Keys without the separator form a group of their own under the full key.
+    pub fn count_by_prefix(&self, separator: char) -> Result<HashMap<String, usize>> {
+        let mut groups = HashMap::new();
+
+        for key in self.keys()? {
+            let prefix = key.split(separator).next().unwrap_or_default();
+            *groups.entry(prefix.to_string()).or_insert(0) += 1;
+        }
+
+        Ok(groups)
+    }
+
+    /// Group keys by whatever `extractor` derives from each document
+    ///
+    /// `extractor` is called with each key and its value as text; keys it
+    /// returns `None` for are left out. Each group's keys are sorted. The
+    /// index is a snapshot handed to the caller, not kept up to date.
+    pub fn build_index<K, F>(&self, extractor: F) -> Result<HashMap<K, Vec<String>>>
+    where
+        K: Eq + Hash,
+        F: Fn(&str, &str) -> Option<K>,
+    {
+        let mut index: HashMap<K, Vec<String>> = HashMap::new();
+        self.for_each_entry(|key, value| {
+            if let Some(index_key) = extractor(key, &String::from_utf8_lossy(value)) {
+                index.entry(index_key).or_default().push(key.to_string());
+            }
+            Ok(())
+        })?;
+
+        for keys in index.values_mut() {
+            keys.sort_unstable();
+        }
+        Ok(index)
+    }
+
+    /// Keys whose value is exactly `value`, sorted
+    ///
+    /// This scans every document in both tiers (O(n), reading cold documents
+    /// from disk), so it suits diagnostics such as finding duplicate values
+    /// rather than hot paths.
+    pub fn keys_with_value(&self, value: &[u8]) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        self.for_each_entry(|key, stored| {
+            if stored == value {
+                keys.push(key.to_string());
+            }
+            Ok(())
+        })?;
+
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Keys whose value is an integer within `[min, max]`, sorted
+    ///
+    /// There is no typed value yet, so a value counts as an integer when it
+    /// is UTF-8 text that parses as an `i64` (surrounding whitespace allowed).
+    /// Every other value is skipped. Like `keys_with_value`, this scans both
+    /// tiers.
+    pub fn find_int_in_range(&self, min: i64, max: i64) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        self.for_each_entry(|key, value| {
+            if aggregate::parse_int(value).is_some_and(|n| (min..=max).contains(&n)) {
+                keys.push(key.to_string());
+            }
+            Ok(())
+        })?;
+
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Every document sorted by value, ties by key
+    ///
+    /// Integer values (as in `find_int_in_range`) compare numerically and
+    /// come before all other values, which compare byte by byte.
+    /// `descending` reverses the order within each of those two groups, so
+    /// integers still come first: the highest score leads a leaderboard.
+    pub fn entries_sorted_by_value(&self, descending: bool) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut entries: Vec<(Option<i64>, String, Vec<u8>)> = self
+            .entries()?
+            .into_iter()
+            .map(|(key, value)| (aggregate::parse_int(&value), key, value))
+            .collect();
+
+        entries.sort_by(|(a_int, a_key, a_value), (b_int, b_key, b_value)| {
+            let by_value = match (a_int, b_int) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (Some(_), None) => return Ordering::Less,
+                (None, Some(_)) => return Ordering::Greater,
+                (None, None) => a_value.cmp(b_value),
+            };
+            let by_value = if descending { by_value.reverse() } else { by_value };
+            by_value.then_with(|| a_key.cmp(b_key))
+        });
+
+        Ok(entries.into_iter().map(|(_, key, value)| (key, value)).collect())
+    }
+
+    /// When a normalized key was last written or touched (Unix seconds)
+    pub(crate) fn updated_at(&self, key: &str) -> Result<Option<u64>> {
+        if let Some(block) = self.hot_data.get(key) {
+            return Ok(Some(block.updated_at));
+        }
+        if self.cold_storage.exists(key) {
+            return self.cold_storage.updated_at(key).map(Some);
+        }
+        Ok(None)
+    }
+
+    /// Keys written or touched at or after `epoch_secs` (Unix seconds)
+    ///
+    /// Sorted most recently updated first, ties by key. Hot documents carry
+    /// their own timestamp; cold ones use the time stored beside their block,
+    /// which moving between tiers preserves, so no values are read.
+    pub fn keys_modified_since(&self, epoch_secs: u64) -> Result<Vec<String>> {
+        let mut updated: Vec<(u64, String)> = self
+            .hot_data
+            .iter()
+            .filter(|(_, block)| block.updated_at >= epoch_secs)
+            .map(|(key, block)| (block.updated_at, key.clone()))
+            .collect();
+
+        for key in self.cold_storage.list_keys()? {
+            if !self.hot_data.contains_key(&key) {
+                let updated_at = self.cold_storage.updated_at(&key)?;
+                if updated_at >= epoch_secs {
+                    updated.push((updated_at, key));
+                }
+            }
+        }
+
+        updated.sort_unstable_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        Ok(updated.into_iter().map(|(_, key)| key).collect())
+    }
+
+    /// The `n` most recently written or touched documents, newest first
+    ///
+    /// Ordered as `keys_modified_since`: update times are kept to the
+    /// second, and writes within the same second are listed by key.
+    pub fn recent(&self, n: usize) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut recent = Vec::with_capacity(n);
+        for key in self.keys_modified_since(0)?.into_iter().take(n) {
+            if let Some(value) = self.peek(&key)? {
+                recent.push((key, value));
+            }
+        }
+        Ok(recent)
+    }
+
+    /// Collect every document from both tiers without promoting anything
+    fn entries(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut entries = Vec::new();
+        self.for_each_entry(|key, value| {
+            entries.push((key.to_string(), value.to_vec()));
+            Ok(())
+        })?;
+        Ok(entries)
+    }
+
+    /// Visit every document from both tiers without promoting anything
+    ///
+    /// Cold documents are loaded one at a time, so large databases can be
+    /// streamed without holding every value in memory.
+    fn for_each_entry<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(&str, &[u8]) -> Result<()>,
+    {
+        if let Some(order) = &self.insertion_order {
+            for key in order {
+                match self.hot_data.get(key) {
+                    Some(block) => f(key, &block.value())?,
+                    None => f(key, self.cold_storage.load(key)?.as_bytes())?,
+                }
+            }
+            return Ok(());
+        }
+
+        for (key, block) in &self.hot_data {
+            f(key, &block.value())?;
+        }
+
+        for key in self.cold_storage.list_keys()? {
+            if !self.hot_data.contains_key(&key) {
+                let block = self.cold_storage.load(&key)?;
+                f(&key, block.as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Promote a document from cold tier to hot tier
     pub fn promote(&mut self, key: &str) -> Result<()> {
+        let key = &*self.normalize_key(key);
+
         // Already in hot tier?
         if self.hot_data.contains_key(key) {
             return Ok(());
@@ -156,6 +1066,7 @@ impl BurrowDB {
         // Load from cold tier
         if self.cold_storage.exists(key) {
             let block = self.cold_storage.load(key)?;
+            let block = self.compressed_for_hot(block);
             self.hot_data.insert(key.to_string(), block);
 
             // Evict if needed
@@ -171,6 +1082,8 @@ impl BurrowDB {
 
     /// Demote a document from hot tier to cold tier
     pub fn demote(&mut self, key: &str) -> Result<()> {
+        let key = &*self.normalize_key(key);
+
         if let Some(block) = self.hot_data.remove(key) {
             self.cold_storage.save(key, &block)?;
             Ok(())
@@ -192,16 +1105,108 @@ impl BurrowDB {
         Ok(())
     }
 
+    /// The settings this database is running with
+    pub fn config(&self) -> Config {
+        Config {
+            data_dir: self.cold_storage.data_dir().to_path_buf(),
+            max_hot_blocks: self.max_hot_blocks,
+            exclusive: self.lock.is_some(),
+            compression_threshold: self.compression_threshold,
+            case_insensitive_keys: self.case_insensitive_keys,
+            overwrite_protection: self.overwrite_protection,
+            max_value_size: self.max_value_size,
+            max_key_size: self.max_key_size,
+            log_level: self.log_level,
+            insertion_order: self.insertion_order.is_some(),
+        }
+    }
+
+    /// How long ago this database was opened
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// How full the hot tier is, as hot documents / `max_hot_blocks`
+    ///
+    /// Reaching 1.0 means the next new document triggers LRU eviction.
+    /// `None` if the hot tier has no room at all (`max_hot_blocks` of 0).
+    pub fn load_factor(&self) -> Option<f64> {
+        if self.max_hot_blocks == 0 {
+            return None;
+        }
+        Some(self.hot_data.len() as f64 / self.max_hot_blocks as f64)
+    }
+
     /// Get database statistics
     pub fn stats(&self) -> DatabaseStats {
-        let total_hot_size: usize = self.hot_data
-            .values()
-            .map(|block| block.as_bytes().len())
-            .sum();
+        let blocks = self.hot_data.values();
 
         DatabaseStats {
             hot_blocks: self.hot_data.len(),
-            total_hot_size,
+            total_hot_size: blocks.clone().map(|block| block.as_bytes().len()).sum(),
+            raw_hot_size: blocks.clone().map(DocumentBlock::value_len).sum(),
+            compressed_blocks: blocks.filter(|block| block.is_compressed()).count(),
+        }
+    }
+
+    /// Compress a block about to enter the hot tier, if compression is on
+    fn compressed_for_hot(&self, mut block: DocumentBlock) -> DocumentBlock {
+        if let Some(threshold) = self.compression_threshold {
+            block.compress_above(threshold);
+        }
+        block
+    }
+
+    /// Append a normalized key to the insertion order if it is new
+    fn record_insertion(&mut self, key: &str) {
+        if self.insertion_order.is_some() && !self.is_stored(key) {
+            if let Some(order) = &mut self.insertion_order {
+                order.push(key.to_string());
+            }
+        }
+    }
+
+    /// Enforce the size limits and the validator, if any, on a write
+    pub(crate) fn check_write(&self, key: &str, value: &[u8]) -> Result<()> {
+        self.check_sizes(key, value)?;
+        match &self.validator {
+            Some(validator) => validator(key, value).map_err(BurrowError::ValidationFailed),
+            None => Ok(()),
+        }
+    }
+
+    /// Enforce the configured key and value size limits
+    fn check_sizes(&self, key: &str, value: &[u8]) -> Result<()> {
+        if let Some(limit) = self.max_key_size {
+            if key.len() > limit {
+                return Err(BurrowError::KeyTooLarge { size: key.len(), limit });
+            }
+        }
+
+        if let Some(limit) = self.max_value_size {
+            if value.len() > limit {
+                return Err(BurrowError::ValueTooLarge { size: value.len(), limit });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply the configured key normalization to a borrowed key
+    fn normalize_key<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        if self.case_insensitive_keys {
+            Cow::Owned(key.to_lowercase())
+        } else {
+            Cow::Borrowed(key)
+        }
+    }
+
+    /// Apply the configured key normalization to an owned key
+    fn normalize_owned_key(&self, key: String) -> String {
+        if self.case_insensitive_keys {
+            key.to_lowercase()
+        } else {
+            key
         }
     }
 
@@ -222,10 +1227,575 @@ impl BurrowDB {
         // Evict the oldest entries
         for (key, _) in entries.into_iter().take(evict_count) {
             if let Some(block) = self.hot_data.remove(&key) {
+                if let Some(hook) = &mut self.evict_hook {
+                    hook(&key, &block.value());
+                }
                 self.cold_storage.save(&key, &block)?;
+                self.log(LogLevel::Info, "evict", &key, &"moved to cold tier");
             }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn test_db(temp_dir: &TempDir) -> BurrowDB {
+        BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap()
+    }
+
+    #[test]
+    fn test_database_is_send_and_sync() {
+        // The server shares one database across tokio tasks
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<BurrowDB>();
+    }
+
+    #[test]
+    fn test_case_insensitive_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir).with_case_insensitive_keys().unwrap();
+
+        db.put_raw("User:1".to_string(), b"alice".to_vec()).unwrap();
+        assert_eq!(db.get("USER:1").unwrap().unwrap(), b"alice");
+        assert_eq!(db.keys().unwrap(), vec!["user:1".to_string()]);
+
+        db.delete("uSeR:1").unwrap();
+        assert!(!db.contains_key("user:1"));
+    }
+
+    #[test]
+    fn test_case_insensitive_keys_rejects_mixed_case_data() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        db.put_raw("User:1".to_string(), b"alice".to_vec()).unwrap();
+
+        assert!(matches!(
+            db.with_case_insensitive_keys(),
+            Err(BurrowError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_access_counts_survive_overwrite_and_reset_on_delete() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+
+        db.put_raw("hot".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("warm".to_string(), b"1".to_vec()).unwrap();
+        for _ in 0..3 {
+            db.get("hot").unwrap();
+        }
+        db.get("warm").unwrap();
+
+        db.put_raw("hot".to_string(), b"2".to_vec()).unwrap();
+        assert_eq!(db.access_count("hot"), 3);
+        assert_eq!(db.top_keys(1), vec![("hot", 3)]);
+
+        db.delete("hot").unwrap();
+        assert_eq!(db.access_count("hot"), 0);
+        assert_eq!(db.top_keys(5), vec![("warm", 1)]);
+    }
+
+    #[test]
+    fn test_scan_cursor_pages_through_sorted_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        for key in ["d", "b", "e", "a", "c"] {
+            db.put_raw(key.to_string(), b"1".to_vec()).unwrap();
+        }
+        db.demote("b").unwrap();
+
+        let (page, cursor) = db.scan_cursor(0, 2).unwrap();
+        assert_eq!((page, cursor), (vec!["a".to_string(), "b".to_string()], 2));
+        let (page, cursor) = db.scan_cursor(cursor, 2).unwrap();
+        assert_eq!((page, cursor), (vec!["c".to_string(), "d".to_string()], 4));
+        let (page, cursor) = db.scan_cursor(cursor, 2).unwrap();
+        assert_eq!((page, cursor), (vec!["e".to_string()], 0));
+
+        assert_eq!(db.first_key().unwrap().as_deref(), Some("a"));
+        assert_eq!(db.last_key().unwrap().as_deref(), Some("e"));
+    }
+
+    #[test]
+    fn test_replace_requires_existing_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+
+        assert!(matches!(
+            db.replace("missing", b"1".to_vec()),
+            Err(BurrowError::KeyNotFound(_))
+        ));
+        assert!(!db.contains_key("missing"));
+
+        db.put_raw("k".to_string(), b"old".to_vec()).unwrap();
+        db.demote("k").unwrap();
+        assert_eq!(db.replace("k", b"new".to_vec()).unwrap(), b"old");
+        assert_eq!(db.get("k").unwrap().unwrap(), b"new");
+
+        db.expire("k", Duration::ZERO);
+        assert!(matches!(db.replace("k", b"newer".to_vec()), Err(BurrowError::KeyNotFound(_))));
+        assert!(db.peek("k").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_find_int_in_range_skips_non_integers() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        for (key, value) in [("a", "-5"), ("b", "10"), ("c", "42"), ("d", "ten"), ("e", "7.5")] {
+            db.put_raw(key.to_string(), value.as_bytes().to_vec()).unwrap();
+        }
+        db.demote("c").unwrap();
+
+        assert_eq!(db.find_int_in_range(-5, 10).unwrap(), vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(db.find_int_in_range(11, i64::MAX).unwrap(), vec!["c".to_string()]);
+    }
+
+    #[test]
+    fn test_build_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        for (key, value) in [("u1", "red"), ("u2", "blue"), ("u3", "red"), ("u4", "")] {
+            db.put_raw(key.to_string(), value.as_bytes().to_vec()).unwrap();
+        }
+        db.demote("u3").unwrap();
+
+        let by_colour = db.build_index(|_, value| (!value.is_empty()).then(|| value.to_string())).unwrap();
+        assert_eq!(by_colour.len(), 2);
+        assert_eq!(by_colour["red"], ["u1", "u3"]);
+        assert_eq!(by_colour["blue"], ["u2"]);
+
+        let by_length = db.build_index(|_, value| Some(value.len())).unwrap();
+        assert_eq!(by_length[&3], ["u1", "u3"]);
+    }
+
+    #[test]
+    fn test_copy_keeps_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        db.put_raw("template".to_string(), b"{}".to_vec()).unwrap();
+        db.put_raw("taken".to_string(), b"mine".to_vec()).unwrap();
+        db.demote("template").unwrap();
+
+        db.copy("template", "fresh", false).unwrap();
+        assert_eq!(db.get("fresh").unwrap().unwrap(), b"{}");
+        assert_eq!(db.get("template").unwrap().unwrap(), b"{}");
+
+        assert!(matches!(db.copy("template", "taken", false), Err(BurrowError::KeyExists(_))));
+        assert_eq!(db.get("taken").unwrap().unwrap(), b"mine");
+        db.copy("template", "taken", true).unwrap();
+        assert_eq!(db.get("taken").unwrap().unwrap(), b"{}");
+
+        assert!(matches!(db.copy("missing", "x", true), Err(BurrowError::KeyNotFound(_))));
+        assert!(!db.contains_key("x"));
+    }
+
+    #[test]
+    fn test_copy_from_an_expired_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        db.put_raw("session".to_string(), b"1".to_vec()).unwrap();
+        db.expire("session", Duration::ZERO);
+
+        assert!(matches!(db.copy("session", "kept", false), Err(BurrowError::KeyNotFound(_))));
+        assert!(!db.contains_key("kept"));
+        assert!(db.peek("kept").unwrap().is_none());
+        assert!(db.peek("session").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reserve_avoids_regrowth() {
+        // Count how often the hot tier reallocates while 500 documents go in
+        let growths = |db: &mut BurrowDB| {
+            let mut growths = 0;
+            let mut capacity = db.hot_data.capacity();
+            for i in 0..500 {
+                db.put_raw(format!("key-{}", i), b"v".to_vec()).unwrap();
+                if db.hot_data.capacity() != capacity {
+                    capacity = db.hot_data.capacity();
+                    growths += 1;
+                }
+            }
+            growths
+        };
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut plain = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 1000).unwrap();
+        let mut reserved = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 1000).unwrap();
+        assert_eq!(reserved.reserve(500), 500);
+
+        assert!(growths(&mut plain) > 0);
+        assert_eq!(growths(&mut reserved), 0);
+        assert_eq!(reserved.reserve(5000), 500);
+    }
+
+    #[test]
+    fn test_incr() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+
+        assert_eq!(db.incr("hits", 5).unwrap(), 5);
+        assert_eq!(db.incr("hits", -7).unwrap(), -2);
+        assert_eq!(db.get("hits").unwrap().unwrap(), b"-2");
+
+        db.put_raw("name".to_string(), b"burrow".to_vec()).unwrap();
+        assert!(matches!(db.incr("name", 1), Err(BurrowError::InvalidDocument(_))));
+        db.put_raw("max".to_string(), i64::MAX.to_string().into_bytes()).unwrap();
+        assert!(db.incr("max", 1).is_err());
+        assert_eq!(db.get_i64("max").unwrap(), Some(i64::MAX));
+    }
+
+    #[test]
+    fn test_getset_returns_previous_value() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir).with_overwrite_protection(true);
+
+        assert_eq!(db.getset("hits", b"0".to_vec()).unwrap(), None);
+        db.put_raw_force("hits".to_string(), b"41".to_vec()).unwrap();
+        db.demote("hits").unwrap();
+        assert_eq!(db.getset("hits", b"0".to_vec()).unwrap(), Some(b"41".to_vec()));
+        assert_eq!(db.get("hits").unwrap().unwrap(), b"0");
+
+        db.expire("hits", Duration::ZERO);
+        assert_eq!(db.getset("hits", b"1".to_vec()).unwrap(), None);
+        assert_eq!(db.ttl("hits"), None);
+    }
+
+    #[test]
+    fn test_entries_sorted_by_value() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        for (key, value) in [("a", "10"), ("b", "9"), ("c", "apple"), ("d", "10"), ("e", "Zed")] {
+            db.put_raw(key.to_string(), value.as_bytes().to_vec()).unwrap();
+        }
+        db.demote("a").unwrap();
+
+        let keys = |descending| -> Vec<String> {
+            let entries = db.entries_sorted_by_value(descending).unwrap();
+            entries.into_iter().map(|(key, _)| key).collect()
+        };
+        assert_eq!(keys(false), ["b", "a", "d", "e", "c"]);
+        assert_eq!(keys(true), ["a", "d", "b", "c", "e"]);
+    }
+
+    #[test]
+    fn test_bulk_load_matches_individual_puts() {
+        // Enough keys to spill past the hot tier, with separators that can't
+        // appear in a file name as written
+        let entries: Vec<(String, Vec<u8>)> = (0..50)
+            .map(|i| (format!("ns:key/{}", i % 40), format!("value {}", i).into_bytes()))
+            .collect();
+
+        let bulk_dir = TempDir::new().unwrap();
+        let mut bulk = BurrowDB::with_config(bulk_dir.path().to_str().unwrap(), 10).unwrap();
+        bulk.put_raw("ns:key/3".to_string(), b"stale".to_vec()).unwrap();
+        assert_eq!(bulk.bulk_load(entries.clone()).unwrap(), 50);
+
+        let single_dir = TempDir::new().unwrap();
+        let mut single = BurrowDB::with_config(single_dir.path().to_str().unwrap(), 10).unwrap();
+        single.put_raw("ns:key/3".to_string(), b"stale".to_vec()).unwrap();
+        for (key, value) in entries {
+            single.put_raw(key, value).unwrap();
+        }
+
+        assert!(bulk.diff(&single).unwrap().is_empty());
+        let mut keys = bulk.keys().unwrap();
+        keys.sort();
+        let mut expected: Vec<String> = (0..40).map(|i| format!("ns:key/{}", i)).collect();
+        expected.sort();
+        assert_eq!(keys, expected);
+        assert!(bulk.stats().hot_blocks < 40);
+
+        let mut bulk = bulk.with_overwrite_protection(true);
+        let again = vec![
+            ("new".to_string(), b"1".to_vec()),
+            ("ns:key/7".to_string(), b"2".to_vec()),
+        ];
+        assert!(matches!(
+            bulk.bulk_load(again),
+            Err(BurrowError::KeyExists(key)) if key == "ns:key/7"
+        ));
+        assert_eq!(bulk.get("ns:key/7").unwrap().unwrap(), b"value 47");
+    }
+
+    #[test]
+    fn test_delete_matching() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        for key in ["user-1", "user-2", "order-1"] {
+            db.put_raw(key.to_string(), b"1".to_vec()).unwrap();
+        }
+        db.demote("user-2").unwrap();
+
+        assert_eq!(db.keys_matching("user-?").unwrap(), vec!["user-1", "user-2"]);
+        assert_eq!(db.delete_matching("user-*").unwrap(), 2);
+        assert_eq!(db.keys().unwrap(), vec!["order-1".to_string()]);
+    }
+
+    #[test]
+    fn test_load_factor_tracks_hot_tier() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 4).unwrap();
+        assert_eq!(db.load_factor(), Some(0.0));
+
+        db.put_raw("a".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("b".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("c".to_string(), b"1".to_vec()).unwrap();
+        assert_eq!(db.load_factor(), Some(0.75));
+    }
+
+    #[test]
+    fn test_uptime_grows() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = test_db(&temp_dir);
+        let first = db.uptime();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(db.uptime() >= first + Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_rename_prefix() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        for key in ["user-1", "user-2", "order-1"] {
+            db.put_raw(key.to_string(), key.as_bytes().to_vec()).unwrap();
+        }
+        db.demote("user-2").unwrap();
+
+        assert_eq!(db.rename_prefix("user-", "member-").unwrap(), 2);
+        let mut keys = db.keys().unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["member-1", "member-2", "order-1"]);
+        assert_eq!(db.get("member-2").unwrap().unwrap(), b"user-2");
+
+        // "member-" -> "order-" would overwrite order-1
+        assert!(matches!(
+            db.rename_prefix("member-", "order-"),
+            Err(BurrowError::KeyExists(key)) if key == "order-1"
+        ));
+        assert!(db.contains_key("member-1"));
+
+        // Renamed keys may take each other's old names
+        db.put_raw("x".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("xx".to_string(), b"2".to_vec()).unwrap();
+        assert_eq!(db.rename_prefix("x", "xx").unwrap(), 2);
+        assert_eq!(db.get("xx").unwrap().unwrap(), b"1");
+        assert_eq!(db.get("xxx").unwrap().unwrap(), b"2");
+    }
+
+    #[test]
+    fn test_insertion_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        db.put_raw("z".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("y".to_string(), b"1".to_vec()).unwrap();
+        let mut db = db.with_insertion_order().unwrap();
+
+        for key in ["c", "a", "b"] {
+            db.put_raw(key.to_string(), key.as_bytes().to_vec()).unwrap();
+        }
+        db.demote("a").unwrap();
+        db.put_raw("c".to_string(), b"again".to_vec()).unwrap();
+        db.delete("y").unwrap();
+        db.put_raw("y".to_string(), b"back".to_vec()).unwrap();
+
+        assert_eq!(db.keys().unwrap(), vec!["z", "c", "a", "b", "y"]);
+        let entries: Vec<String> = db.entries().unwrap().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(entries, db.keys().unwrap());
+    }
+
+    #[test]
+    fn test_modify_hot_and_cold() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+
+        db.put_raw("log".to_string(), b"a".to_vec()).unwrap();
+        assert!(db.modify("log", |v| v.extend_from_slice(b"b")).unwrap());
+
+        db.demote("log").unwrap();
+        assert!(db.modify("log", |v| v.extend_from_slice(b"c")).unwrap());
+        assert_eq!(db.get("log").unwrap().unwrap(), b"abc");
+
+        assert!(!db.modify("missing", |_| panic!("closure must not run")).unwrap());
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir).with_max_value_size(4).with_max_key_size(8);
+
+        db.put_raw("short".to_string(), b"1234".to_vec()).unwrap();
+        assert!(matches!(
+            db.put_raw("short".to_string(), b"12345".to_vec()),
+            Err(BurrowError::ValueTooLarge { size: 5, limit: 4 })
+        ));
+        assert!(matches!(
+            db.put_raw("much-too-long".to_string(), b"1".to_vec()),
+            Err(BurrowError::KeyTooLarge { size: 13, limit: 8 })
+        ));
+
+        assert!(db.modify("short", |v| v.push(b'5')).is_err());
+        assert_eq!(db.get("short").unwrap().unwrap(), b"1234");
+    }
+
+    #[test]
+    fn test_compression_is_transparent() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir).with_compression_threshold(64);
+        let text = "lorem ipsum dolor sit amet ".repeat(40);
+
+        db.put_raw("big".to_string(), text.clone().into_bytes()).unwrap();
+        db.put_raw("small".to_string(), b"lorem ipsum".to_vec()).unwrap();
+        let stats = db.stats();
+        assert_eq!(stats.compressed_blocks, 1);
+        assert_eq!(stats.raw_hot_size, text.len() + 11);
+        assert!(stats.total_hot_size < stats.raw_hot_size / 4);
+        assert_eq!(db.get("big").unwrap().unwrap(), text.as_bytes());
+        assert_eq!(db.get("small").unwrap().unwrap(), b"lorem ipsum");
+
+        assert!(db.modify("big", |v| v.extend_from_slice(b"!")).unwrap());
+        assert_eq!(db.get("big").unwrap().unwrap(), format!("{}!", text).as_bytes());
+        assert_eq!(db.stats().compressed_blocks, 1);
+
+        // Cold files hold the original bytes
+        db.demote("big").unwrap();
+        let file = fs::read(temp_dir.path().join("big.block")).unwrap();
+        assert_eq!(file, format!("{}!", text).as_bytes());
+        db.promote("big").unwrap();
+        assert_eq!(db.stats().compressed_blocks, 1);
+        assert_eq!(db.get("big").unwrap().unwrap(), file);
+    }
+
+    #[test]
+    fn test_touch_restarts_the_ttl() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        db.put_raw("live".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("cold".to_string(), b"2".to_vec()).unwrap();
+        db.put_raw("fixed".to_string(), b"3".to_vec()).unwrap();
+        for key in ["live", "cold"] {
+            db.expire(key, Duration::from_secs(600));
+            db.expires_at.insert(key.to_string(), SystemTime::now() + Duration::from_secs(5));
+        }
+        db.demote("cold").unwrap();
+        let deadline = SystemTime::now() + Duration::from_secs(5);
+        db.expires_at.insert("fixed".to_string(), deadline);
+
+        for key in ["live", "cold", "fixed"] {
+            assert!(db.touch(key).unwrap(), "{}", key);
+        }
+        assert!(db.ttl("live").unwrap() > Duration::from_secs(590));
+        assert!(db.ttl("cold").unwrap() > Duration::from_secs(590));
+        assert_eq!(db.expires_at["fixed"], deadline);
+        assert!(db.cold_storage.exists("cold"));
+
+        // The length survives a rename, but not a persist
+        db.rename_prefix("li", "LI").unwrap();
+        db.expires_at.insert("LIve".to_string(), SystemTime::now() + Duration::from_secs(5));
+        db.touch("LIve").unwrap();
+        assert!(db.ttl("LIve").unwrap() > Duration::from_secs(590));
+        assert!(db.persist("cold"));
+        db.touch("cold").unwrap();
+        assert_eq!(db.ttl("cold"), None);
+
+        assert!(!db.touch("missing").unwrap());
+    }
+
+    #[test]
+    fn test_touch_does_not_revive_an_expired_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        db.put_raw("hot".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("cold".to_string(), b"2".to_vec()).unwrap();
+        db.demote("cold").unwrap();
+        for key in ["hot", "cold"] {
+            db.expire(key, Duration::ZERO);
+            assert!(!db.touch(key).unwrap(), "{}", key);
+            assert!(!db.contains_key(key));
+            assert!(db.peek(key).unwrap().is_none());
+        }
+        assert!(db.expired_keys().is_empty());
+    }
+
+    #[test]
+    fn test_keys_modified_since() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        db.put_raw("old".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("cold".to_string(), b"2".to_vec()).unwrap();
+        db.put_raw("new".to_string(), b"3".to_vec()).unwrap();
+        db.demote("cold").unwrap();
+
+        let now = db.hot_data["new"].updated_at;
+        db.hot_data.get_mut("old").unwrap().updated_at = now - 100;
+        db.hot_data.get_mut("new").unwrap().updated_at = now + 5;
+
+        assert_eq!(db.keys_modified_since(now).unwrap(), vec!["new", "cold"]);
+        assert_eq!(db.keys_modified_since(now - 100).unwrap(), vec!["new", "cold", "old"]);
+        assert!(db.keys_modified_since(now + 6).unwrap().is_empty());
+
+        let recent = db.recent(2).unwrap();
+        assert_eq!(recent, vec![
+            ("new".to_string(), b"3".to_vec()),
+            ("cold".to_string(), b"2".to_vec()),
+        ]);
+    }
+
+    #[test]
+    fn test_moving_tiers_is_not_a_modification() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir);
+        for key in ["evicted", "demoted", "flushed"] {
+            db.put_raw(key.to_string(), b"1".to_vec()).unwrap();
+        }
+        db.put_raw("fresh".to_string(), b"2".to_vec()).unwrap();
+
+        let now = db.hot_data["fresh"].updated_at;
+        for key in ["evicted", "demoted", "flushed"] {
+            db.hot_data.get_mut(key).unwrap().updated_at = now - 100;
+        }
+        db.demote("demoted").unwrap();
+        db.max_hot_blocks = 2;
+        db.hot_data.get_mut("evicted").unwrap().last_accessed = 0;
+        db.evict_lru().unwrap();
+        assert!(!db.hot_data.contains_key("evicted"));
+
+        assert_eq!(db.keys_modified_since(now).unwrap(), vec!["fresh"]);
+        db.flush_all().unwrap();
+        assert_eq!(db.keys_modified_since(now).unwrap(), vec!["fresh"]);
+        assert_eq!(db.recent(4).unwrap()[0].0, "fresh");
+
+        db.max_hot_blocks = 100;
+        db.promote("evicted").unwrap();
+        assert_eq!(db.hot_data["evicted"].updated_at, now - 100);
+        db.touch("demoted").unwrap();
+        assert_eq!(db.keys_modified_since(now).unwrap(), vec!["demoted", "fresh"]);
+    }
+
+    #[test]
+    fn test_overwrite_protection() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = test_db(&temp_dir).with_overwrite_protection(true);
+
+        db.put_raw("k".to_string(), b"1".to_vec()).unwrap();
+        assert!(matches!(
+            db.put_raw("k".to_string(), b"2".to_vec()),
+            Err(BurrowError::KeyExists(key)) if key == "k"
+        ));
+        assert_eq!(db.get("k").unwrap().unwrap(), b"1");
+
+        db.put_raw_force("k".to_string(), b"2".to_vec()).unwrap();
+        db.replace("k", b"3".to_vec()).unwrap();
+        assert_eq!(db.get("k").unwrap().unwrap(), b"3");
+
+        let mut db = db.with_overwrite_protection(false);
+        db.put_raw("k".to_string(), b"4".to_vec()).unwrap();
+        assert_eq!(db.get("k").unwrap().unwrap(), b"4");
+    }
+}
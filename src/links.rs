@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+/// Directed, labelled relationships between keys
+///
+/// Links are kept in two maps so both "what does this key point to" and
+/// "what points at this key" are answered without scanning every link.
+#[derive(Default)]
+pub(crate) struct LinkIndex {
+    /// Forward index: from → [(relationship, to)]
+    outgoing: HashMap<String, Vec<(String, String)>>,
+    /// Reverse index: to → [(from, relationship)]
+    incoming: HashMap<String, Vec<(String, String)>>,
+}
+
+impl LinkIndex {
+    /// Record a `from -[rel]-> to` link (duplicates are ignored)
+    pub(crate) fn add(&mut self, from: &str, rel: &str, to: &str) {
+        let forward = self.outgoing.entry(from.to_string()).or_default();
+        if forward.iter().any(|(r, t)| r == rel && t == to) {
+            return;
+        }
+        forward.push((rel.to_string(), to.to_string()));
+
+        self.incoming
+            .entry(to.to_string())
+            .or_default()
+            .push((from.to_string(), rel.to_string()));
+    }
+
+    /// Keys with at least one link pointing at `target`, sorted and deduplicated
+    pub(crate) fn linked_to(&self, target: &str) -> Vec<&str> {
+        let mut sources: Vec<&str> = self.incoming
+            .get(target)
+            .map(|links| links.iter().map(|(from, _)| from.as_str()).collect())
+            .unwrap_or_default();
+
+        sources.sort_unstable();
+        sources.dedup();
+        sources
+    }
+
+    /// Links starting at `from` as `(rel, to)`, sorted
+    pub(crate) fn outgoing_of(&self, from: &str) -> Vec<(&str, &str)> {
+        sorted_pairs(self.outgoing.get(from))
+    }
+
+    /// Links ending at `to` as `(from, rel)`, sorted
+    pub(crate) fn incoming_of(&self, to: &str) -> Vec<(&str, &str)> {
+        sorted_pairs(self.incoming.get(to))
+    }
+
+    /// Every link as `(from, rel, to)`, in no particular order
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.outgoing.iter().flat_map(|(from, links)| {
+            links.iter().map(move |(rel, to)| (from.as_str(), rel.as_str(), to.as_str()))
+        })
+    }
+
+    /// Whether the forward and reverse maps describe the same links
+    pub(crate) fn is_consistent(&self) -> bool {
+        let reverse_count: usize = self.incoming.values().map(Vec::len).sum();
+        reverse_count == self.iter().count()
+            && self.iter().all(|(from, rel, to)| {
+                self.incoming
+                    .get(to)
+                    .is_some_and(|links| links.iter().any(|(f, r)| f == from && r == rel))
+            })
+    }
+
+    /// Drop every link that starts or ends at `key`
+    pub(crate) fn remove_key(&mut self, key: &str) {
+        if let Some(forward) = self.outgoing.remove(key) {
+            for (_, to) in forward {
+                if let Some(reverse) = self.incoming.get_mut(&to) {
+                    reverse.retain(|(from, _)| from != key);
+                    if reverse.is_empty() {
+                        self.incoming.remove(&to);
+                    }
+                }
+            }
+        }
+
+        if let Some(reverse) = self.incoming.remove(key) {
+            for (from, _) in reverse {
+                if let Some(forward) = self.outgoing.get_mut(&from) {
+                    forward.retain(|(_, to)| to != key);
+                    if forward.is_empty() {
+                        self.outgoing.remove(&from);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn sorted_pairs(pairs: Option<&Vec<(String, String)>>) -> Vec<(&str, &str)> {
+    let mut pairs: Vec<(&str, &str)> = pairs
+        .map(|pairs| pairs.iter().map(|(a, b)| (a.as_str(), b.as_str())).collect())
+        .unwrap_or_default();
+    pairs.sort_unstable();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_key_cleans_both_directions() {
+        let mut index = LinkIndex::default();
+        index.add("order:1", "placed_by", "user:1");
+        index.add("order:2", "placed_by", "user:1");
+        index.add("user:1", "lives_in", "city:1");
+
+        assert_eq!(index.linked_to("user:1"), vec!["order:1", "order:2"]);
+
+        index.remove_key("user:1");
+        assert!(index.linked_to("user:1").is_empty());
+        assert!(index.linked_to("city:1").is_empty());
+        assert!(!index.outgoing.contains_key("order:1"));
+    }
+}
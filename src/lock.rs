@@ -0,0 +1,98 @@
+//! Exclusive access to a data directory
+//!
+//! Two processes sharing a data directory can each overwrite the other's
+//! block files, so a database opened with `BurrowDBBuilder::exclusive`
+//! holds a `burrow.lock` file there until it is dropped. The lock is
+//! advisory: only databases that ask for it check it.
+//!
+//! A process that crashes leaves its lock behind. `BurrowDB::force_unlock`
+//! (the shell's `--force-unlock` flag) removes it; only use it once the
+//! process named in the lock file is known to be gone.
+
+use crate::error::{BurrowError, Result};
+use crate::BurrowDB;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const LOCK_FILE: &str = "burrow.lock";
+
+/// A held lock on a data directory, released on drop
+pub(crate) struct DirLock {
+    path: PathBuf,
+}
+
+impl DirLock {
+    /// Take the lock on `data_dir`, failing with `Locked` if it is held
+    pub(crate) fn acquire(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(LOCK_FILE);
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                let holder = fs::read_to_string(&path).unwrap_or_default();
+                return Err(BurrowError::Locked(format!(
+                    "{} is held by process {}",
+                    path.display(),
+                    holder.trim()
+                )));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if let Err(e) = writeln!(file, "{}", std::process::id()) {
+            let _ = fs::remove_file(&path);
+            return Err(e.into());
+        }
+        Ok(Self { path })
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl BurrowDB {
+    /// Remove a lock left on `data_dir` by a process that didn't exit cleanly
+    ///
+    /// Returns whether there was a lock to remove. Removing a lock that a
+    /// running database still holds lets a second one in, so check that
+    /// its owner is gone first.
+    pub fn force_unlock<P: AsRef<Path>>(data_dir: P) -> Result<bool> {
+        match fs::remove_file(data_dir.as_ref().join(LOCK_FILE)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_exclusive_open_is_refused_while_locked() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().to_str().unwrap();
+        let open = || BurrowDB::builder().data_dir(dir).exclusive(true).build();
+
+        let mut db = open().unwrap();
+        db.put_raw("k".to_string(), b"v".to_vec()).unwrap();
+        assert!(matches!(open(), Err(BurrowError::Locked(_))));
+        assert!(BurrowDB::builder().data_dir(dir).build().is_ok());
+        assert_eq!(db.keys().unwrap(), vec!["k".to_string()]);
+
+        drop(db);
+        let db = open().unwrap();
+
+        // A crashed owner never drops its lock; forget stands in for that
+        std::mem::forget(db);
+        assert!(open().is_err());
+        assert!(BurrowDB::force_unlock(dir).unwrap());
+        assert!(!BurrowDB::force_unlock(dir).unwrap());
+        assert!(open().is_ok());
+    }
+}
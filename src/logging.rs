@@ -0,0 +1,97 @@
+//! Operation logging to stderr
+//!
+//! A small built-in alternative to the `log` crate, keeping the core free of
+//! dependencies. Each line is `key=value` pairs so it can be grepped or
+//! parsed:
+//!
+//! ```text
+//! [burrow] level=debug op=get key="user:1" outcome=found
+//! ```
+
+use crate::error::Result;
+use crate::BurrowDB;
+use std::fmt;
+use std::str::FromStr;
+
+/// How much the database reports on stderr
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Nothing is logged (the default)
+    #[default]
+    Off,
+    /// Tier movements such as LRU eviction
+    Info,
+    /// Everything at `Info`, plus every put, get and delete
+    Debug,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LogLevel::Off => "off",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    /// Parse a level name, ignoring case
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(LogLevel::Off),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            _ => Err(format!("unknown log level '{}' (expected off, info or debug)", s)),
+        }
+    }
+}
+
+impl BurrowDB {
+    /// Choose which operations are logged to stderr
+    pub fn set_log_level(&mut self, level: LogLevel) {
+        self.log_level = level;
+    }
+
+    /// The current log level
+    pub fn log_level(&self) -> LogLevel {
+        self.log_level
+    }
+
+    /// Write one log line if `level` is enabled
+    pub(crate) fn log(&self, level: LogLevel, op: &str, key: &str, outcome: &dyn fmt::Display) {
+        if level != LogLevel::Off && level <= self.log_level {
+            eprintln!("[burrow] level={} op={} key={:?} outcome={}", level, op, key, outcome);
+        }
+    }
+
+    /// Log the result of an operation at `Debug`, naming successes with `describe`
+    pub(crate) fn log_outcome<T>(
+        &self,
+        op: &str,
+        key: &str,
+        result: &Result<T>,
+        describe: impl FnOnce(&T) -> &'static str,
+    ) {
+        match result {
+            Ok(value) => self.log(LogLevel::Debug, op, key, &describe(value)),
+            Err(e) => self.log(LogLevel::Debug, op, key, &format_args!("error ({})", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_level_parse_and_order() {
+        assert_eq!("DEBUG".parse::<LogLevel>().unwrap(), LogLevel::Debug);
+        assert_eq!("off".parse::<LogLevel>().unwrap(), LogLevel::Off);
+        assert!("verbose".parse::<LogLevel>().is_err());
+        assert!(LogLevel::Off < LogLevel::Info && LogLevel::Info < LogLevel::Debug);
+    }
+}
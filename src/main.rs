@@ -1,17 +1,214 @@
-use burrow_db::BurrowDB;
-use std::io;
+use burrow_db::cli::{self, styled, CLI, DEFAULT_WARN_THRESHOLD};
+use burrow_db::{BurrowDB, BurrowError};
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+/// `println!` and `eprintln!` through `styled`, as the CLI prints
+///
+/// A closed stream is ignored rather than panicking, so piping into `head`
+/// still lets `--save-on-exit` finish.
+macro_rules! say {
+    ($($arg:tt)*) => {{
+        let _ = writeln!(io::stdout(), "{}", styled(&format!($($arg)*)));
+    }};
+}
+macro_rules! complain {
+    ($($arg:tt)*) => {{
+        let _ = writeln!(io::stderr(), "{}", styled(&format!($($arg)*)));
+    }};
+}
+
+/// Where the shell keeps its cold tier, as `BurrowDB::new` does
+const DATA_DIR: &str = "./data";
+
+/// Command-line options for the interactive shell
+#[derive(Default)]
+struct Options {
+    /// File to load with `load_from_file` before the CLI starts
+    load: Option<String>,
+    /// File to persist with `save_to_file` when the CLI exits
+    save_on_exit: Option<String>,
+    /// Largest accepted value in bytes
+    max_value_size: Option<usize>,
+    /// Largest accepted key in bytes
+    max_key_size: Option<usize>,
+    /// Start the CLI with dry-run mode on
+    dry_run: bool,
+    /// Hot tier fill percentage that triggers a warning
+    warn_threshold: Option<f64>,
+    /// List keys in insertion order
+    insertion_order: bool,
+    /// Compress large hot tier values
+    compress: bool,
+    /// Make PUT on an existing key require FORCE
+    protect_overwrites: bool,
+    /// File to load CLI aliases from and save them to
+    aliases: Option<String>,
+    /// Remove a data directory lock left by a crashed shell before opening
+    force_unlock: bool,
+    /// Print ASCII tags instead of emoji
+    plain: bool,
+}
 
 fn main() {
-    let db= BurrowDB::new();
-    println!("Hello, world from BurrowDB!");
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("❌ {}", msg);
+            eprintln!(
+                "Usage: burrow_db [--load <file>] [--save-on-exit <file>] \
+                 [--max-value-size <bytes>] [--max-key-size <bytes>] [--dry-run] \
+                 [--warn-threshold <percent>] [--insertion-order] [--compress] \
+                 [--protect-overwrites] [--aliases <file>] \
+                 [--force-unlock] [--plain]"
+            );
+            process::exit(2);
+        }
+    };
 
-    loop{
-        let mut input=String::new();
-        io::stdin().read_line(&mut input).expect("Failed to read line");
+    cli::set_plain(options.plain);
 
-        let input= input.trim();
+    if options.force_unlock {
+        match BurrowDB::force_unlock(DATA_DIR) {
+            Ok(true) => say!("🔓 Removed stale lock on {}", DATA_DIR),
+            Ok(false) => {}
+            Err(e) => {
+                complain!("❌ Failed to remove lock on {}: {}", DATA_DIR, e);
+                process::exit(1);
+            }
+        }
+    }
 
-        
+    // Exclusive, so a second shell on the same directory can't clobber
+    // this one's block files
+    let mut db = match BurrowDB::builder().data_dir(DATA_DIR).exclusive(true).build() {
+        Ok(db) => db,
+        Err(e @ BurrowError::Locked(_)) => {
+            complain!("❌ Failed to open database: {}", e);
+            complain!("   If no other shell is running, restart with --force-unlock");
+            process::exit(1);
+        }
+        Err(e) => {
+            complain!("❌ Failed to open database: {}", e);
+            process::exit(1);
+        }
+    };
+    if let Some(bytes) = options.max_value_size {
+        db = db.with_max_value_size(bytes);
+    }
+    if let Some(bytes) = options.max_key_size {
+        db = db.with_max_key_size(bytes);
+    }
+    if options.protect_overwrites {
+        db = db.with_overwrite_protection(true);
+    }
+    if options.compress {
+        db = db.with_compression();
+    }
+    if options.insertion_order {
+        db = match db.with_insertion_order() {
+            Ok(db) => db,
+            Err(e) => {
+                complain!("❌ Failed to enable insertion order: {}", e);
+                process::exit(1);
+            }
+        };
+    }
 
+    if let Some(path) = &options.load {
+        match db.load_from_file(path) {
+            Ok(count) => say!("✓ Loaded {} keys from {}", count, path),
+            Err(BurrowError::IoError(e)) if e.kind() == io::ErrorKind::NotFound => {
+                say!("📂 {} not found, starting with an empty database", path);
+            }
+            Err(e) => {
+                complain!("❌ Error loading {}: {}", path, e);
+                drop(db);
+                process::exit(1);
+            }
+        }
     }
+
+    let mut cli = CLI::new(&mut db)
+        .dry_run(options.dry_run)
+        .warn_threshold(options.warn_threshold.unwrap_or(DEFAULT_WARN_THRESHOLD));
+    if let Some(path) = &options.aliases {
+        cli = cli.aliases_file(path);
+    }
+    cli.run();
+    let aborted = cli.aborted();
+
+    if let Some(path) = &options.save_on_exit {
+        match db.save_to_file(path) {
+            Ok(()) => say!("✓ Saved database to {}", path),
+            Err(e) => {
+                complain!("❌ Error saving {}: {}", path, e);
+                drop(db);
+                process::exit(1);
+            }
+        }
+    }
+
+    if aborted {
+        // process::exit skips destructors, and dropping releases the lock
+        drop(db);
+        process::exit(1);
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--load" => {
+                options.load = Some(args.next().ok_or("--load requires a file path")?);
+            }
+            "--aliases" => {
+                options.aliases = Some(args.next().ok_or("--aliases requires a file path")?);
+            }
+            "--save-on-exit" => {
+                options.save_on_exit =
+                    Some(args.next().ok_or("--save-on-exit requires a file path")?);
+            }
+            "--max-value-size" => {
+                options.max_value_size = Some(parse_size(args.next(), "--max-value-size")?);
+            }
+            "--max-key-size" => {
+                options.max_key_size = Some(parse_size(args.next(), "--max-key-size")?);
+            }
+            "--dry-run" => options.dry_run = true,
+            "--insertion-order" => options.insertion_order = true,
+            "--compress" => options.compress = true,
+            "--protect-overwrites" => options.protect_overwrites = true,
+            "--force-unlock" => options.force_unlock = true,
+            "--plain" => options.plain = true,
+            "--warn-threshold" => {
+                options.warn_threshold = Some(parse_percent(args.next(), "--warn-threshold")?);
+            }
+            other => return Err(format!("Unknown argument: {}", other)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_percent(value: Option<String>, flag: &str) -> Result<f64, String> {
+    let percent: f64 = value
+        .ok_or_else(|| format!("{} requires a percentage", flag))?
+        .parse()
+        .map_err(|_| format!("{} expects a number", flag))?;
+
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(format!("{} must be between 0 and 100", flag));
+    }
+    Ok(percent)
+}
+
+fn parse_size(value: Option<String>, flag: &str) -> Result<usize, String> {
+    value
+        .ok_or_else(|| format!("{} requires a size in bytes", flag))?
+        .parse()
+        .map_err(|_| format!("{} expects a whole number of bytes", flag))
 }
@@ -0,0 +1,191 @@
+//! Combining documents from another database
+
+use crate::error::Result;
+use crate::BurrowDB;
+use std::fmt;
+use std::str::FromStr;
+
+/// How `merge_from` resolves a key present in both databases
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep this database's value
+    KeepExisting,
+    /// Take the other database's value
+    Overwrite,
+    /// Take whichever value was updated more recently, keeping this
+    /// database's value on a tie
+    NewestWins,
+}
+
+impl fmt::Display for MergeStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MergeStrategy::KeepExisting => "keep",
+            MergeStrategy::Overwrite => "overwrite",
+            MergeStrategy::NewestWins => "newest",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for MergeStrategy {
+    type Err = String;
+
+    /// Parse a strategy name (keep, overwrite or newest), ignoring case
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "keep" => Ok(MergeStrategy::KeepExisting),
+            "overwrite" => Ok(MergeStrategy::Overwrite),
+            "newest" => Ok(MergeStrategy::NewestWins),
+            _ => Err(format!(
+                "unknown merge strategy '{}' (expected keep, overwrite or newest)",
+                s
+            )),
+        }
+    }
+}
+
+/// What a merge did, or would do, with the other database's keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// Keys that only existed in the other database
+    pub added: usize,
+    /// Keys in both whose value was taken from the other database
+    pub overwritten: usize,
+    /// Keys in both whose value was kept
+    pub skipped: usize,
+}
+
+/// Outcome for a single key
+enum Decision {
+    Add,
+    Overwrite,
+    Skip,
+}
+
+impl BurrowDB {
+    /// Copy documents from `other`, resolving shared keys by `strategy`
+    ///
+    /// Merged values are stored as new writes, so they get a fresh update
+    /// time and any expiry on the key is cleared. Neither database's reads
+    /// are counted and `other` is left unchanged.
+    pub fn merge_from(&mut self, other: &BurrowDB, strategy: MergeStrategy) -> Result<MergeSummary> {
+        let mut summary = MergeSummary::default();
+
+        for key in other.keys()? {
+            let decision = self.decide(other, &key, strategy)?;
+            summary.count(&decision);
+            if matches!(decision, Decision::Skip) {
+                continue;
+            }
+            if let Some(value) = other.peek(&key)? {
+                self.put_raw_force(key, value)?;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// What `merge_from` with the same arguments would do, without doing it
+    pub fn merge_preview(&self, other: &BurrowDB, strategy: MergeStrategy) -> Result<MergeSummary> {
+        let mut summary = MergeSummary::default();
+        for key in other.keys()? {
+            summary.count(&self.decide(other, &key, strategy)?);
+        }
+        Ok(summary)
+    }
+
+    fn decide(&self, other: &BurrowDB, key: &str, strategy: MergeStrategy) -> Result<Decision> {
+        let Some(mine) = self.updated_at(&self.normalize_key(key))? else {
+            return Ok(Decision::Add);
+        };
+
+        Ok(match strategy {
+            MergeStrategy::KeepExisting => Decision::Skip,
+            MergeStrategy::Overwrite => Decision::Overwrite,
+            MergeStrategy::NewestWins => match other.updated_at(key)? {
+                Some(theirs) if theirs > mine => Decision::Overwrite,
+                _ => Decision::Skip,
+            },
+        })
+    }
+}
+
+impl MergeSummary {
+    fn count(&mut self, decision: &Decision) {
+        match decision {
+            Decision::Add => self.added += 1,
+            Decision::Overwrite => self.overwritten += 1,
+            Decision::Skip => self.skipped += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn pair(temp_dir: &TempDir) -> (BurrowDB, BurrowDB) {
+        let mut mine = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        let mut theirs = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100).unwrap();
+        mine.put_raw("shared-new".to_string(), b"mine".to_vec()).unwrap();
+        mine.put_raw("shared-old".to_string(), b"mine".to_vec()).unwrap();
+        theirs.put_raw("shared-new".to_string(), b"theirs".to_vec()).unwrap();
+        theirs.put_raw("shared-old".to_string(), b"theirs".to_vec()).unwrap();
+        theirs.put_raw("only-theirs".to_string(), b"theirs".to_vec()).unwrap();
+
+        let now = mine.hot_data["shared-new"].updated_at;
+        theirs.hot_data.get_mut("shared-new").unwrap().updated_at = now + 10;
+        theirs.hot_data.get_mut("shared-old").unwrap().updated_at = now - 10;
+        (mine, theirs)
+    }
+
+    #[test]
+    fn test_merge_strategies() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let (mut mine, theirs) = pair(&temp_dir);
+        let summary = mine.merge_from(&theirs, MergeStrategy::KeepExisting).unwrap();
+        assert_eq!(summary, MergeSummary { added: 1, overwritten: 0, skipped: 2 });
+        assert_eq!(mine.get("shared-new").unwrap().unwrap(), b"mine");
+        assert_eq!(mine.get("only-theirs").unwrap().unwrap(), b"theirs");
+
+        let (mut mine, theirs) = pair(&temp_dir);
+        let preview = mine.merge_preview(&theirs, MergeStrategy::NewestWins).unwrap();
+        let summary = mine.merge_from(&theirs, MergeStrategy::NewestWins).unwrap();
+        assert_eq!(preview, summary);
+        assert_eq!(summary, MergeSummary { added: 1, overwritten: 1, skipped: 1 });
+        assert_eq!(mine.get("shared-new").unwrap().unwrap(), b"theirs");
+        assert_eq!(mine.get("shared-old").unwrap().unwrap(), b"mine");
+
+        let (mut mine, theirs) = pair(&temp_dir);
+        let summary = mine.merge_from(&theirs, MergeStrategy::Overwrite).unwrap();
+        assert_eq!(summary, MergeSummary { added: 1, overwritten: 2, skipped: 0 });
+        assert_eq!(mine.get("shared-old").unwrap().unwrap(), b"theirs");
+
+        assert_eq!("NEWEST".parse(), Ok(MergeStrategy::NewestWins));
+        assert!("latest".parse::<MergeStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_merge_from_cold_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let (mut mine, mut theirs) = pair(&temp_dir);
+        theirs.put_raw("ns:cold/1".to_string(), b"theirs".to_vec()).unwrap();
+        for key in ["shared-new", "shared-old", "only-theirs", "ns:cold/1"] {
+            theirs.demote(key).unwrap();
+        }
+        let now = mine.hot_data["shared-old"].updated_at;
+        mine.hot_data.get_mut("shared-old").unwrap().updated_at = now - 5;
+        mine.demote("shared-old").unwrap();
+
+        // Update times come from the cold tier, not when the files were written
+        let summary = mine.merge_from(&theirs, MergeStrategy::NewestWins).unwrap();
+        assert_eq!(summary, MergeSummary { added: 2, overwritten: 1, skipped: 1 });
+        assert_eq!(mine.get("ns:cold/1").unwrap().unwrap(), b"theirs");
+        assert_eq!(mine.get("shared-new").unwrap().unwrap(), b"theirs");
+        assert_eq!(mine.get("shared-old").unwrap().unwrap(), b"mine");
+        assert!(theirs.hot_data.is_empty());
+    }
+}
@@ -0,0 +1,45 @@
+//! Small deterministic pseudo-random number generator
+//!
+//! A 64-bit linear congruential generator (Knuth's MMIX constants). The
+//! same seed always gives the same sequence, which is what sampling for
+//! reproducible debugging needs; it is not suitable for anything secret.
+
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        // The low bits of an LCG have short periods, so return the high half
+        // mixed into the low half
+        self.state ^ (self.state >> 32)
+    }
+
+    /// A value in `0..bound`; `bound` must be non-zero
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let first: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..5).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first, (0..5).map(|_| Rng::new(43).next_u64()).collect::<Vec<_>>());
+        assert!((0..100).all(|_| a.below(7) < 7));
+    }
+}
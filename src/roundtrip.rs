@@ -0,0 +1,98 @@
+//! Save format round-trip checks
+//!
+//! Each format is written to memory and parsed back, and the result is
+//! compared with the live data. A mismatch means that format would not
+//! faithfully restore the database, e.g. the JSON and CSV exports, which
+//! store values as text, can't carry values that aren't valid UTF-8.
+
+use crate::binary_format;
+use crate::csv_format::parse_csv_records;
+use crate::error::{BurrowError, Result};
+use crate::json_format::parse_jsonl_record;
+use crate::text_format::{decode_text, TextFormat};
+use crate::BurrowDB;
+use std::collections::{BTreeSet, HashMap};
+
+/// Formats checked by `roundtrip_mismatches`, in report order
+pub const ROUNDTRIP_FORMATS: &[&str] = &["text", "binary", "jsonl", "csv"];
+
+impl BurrowDB {
+    /// Whether every save format restores exactly the current data
+    ///
+    /// Errors while encoding or decoding count as a failure.
+    pub fn roundtrip_ok(&self) -> bool {
+        self.roundtrip_mismatches().is_ok_and(|mismatches| mismatches.is_empty())
+    }
+
+    /// Keys that don't survive a round trip, as (format, key) pairs
+    ///
+    /// Each format in `ROUNDTRIP_FORMATS` is written to memory and parsed
+    /// back. A key is reported if its value comes back different, missing or
+    /// appears from nowhere. Pairs are grouped by format, keys sorted.
+    pub fn roundtrip_mismatches(&self) -> Result<Vec<(&'static str, String)>> {
+        let original: HashMap<String, Vec<u8>> = self.entries()?.into_iter().collect();
+        let mut mismatches = Vec::new();
+
+        for &format in ROUNDTRIP_FORMATS {
+            let restored: HashMap<String, Vec<u8>> = self.reparse(format)?.into_iter().collect();
+            let keys: BTreeSet<&String> = original.keys().chain(restored.keys()).collect();
+            for key in keys {
+                if original.get(key) != restored.get(key) {
+                    mismatches.push((format, key.clone()));
+                }
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    /// Write every document in `format` and parse the output back
+    fn reparse(&self, format: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        match format {
+            "text" => decode_text(&self.encode_text(&TextFormat::default())?),
+            "binary" => binary_format::decode(&self.encode_binary()?),
+            "jsonl" => {
+                let mut out = Vec::new();
+                self.write_jsonl(&mut out)?;
+                String::from_utf8_lossy(&out)
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| {
+                        let (key, value) =
+                            parse_jsonl_record(line).map_err(BurrowError::SerializationError)?;
+                        Ok((key, value.into_bytes()))
+                    })
+                    .collect()
+            }
+            "csv" => {
+                let mut out = Vec::new();
+                self.write_csv(&mut out)?;
+                parse_csv_records(&String::from_utf8_lossy(&out))
+            }
+            other => Err(BurrowError::InvalidConfig(format!("unknown format '{}'", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_roundtrip_reports_lossy_formats() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 2).unwrap();
+        db.put_raw("name".to_string(), b"Alice, \"Al\"\nSmith".to_vec()).unwrap();
+        db.put_raw("tab\tkey".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("cold".to_string(), b"evicted".to_vec()).unwrap();
+        assert!(db.roundtrip_ok());
+
+        db.put_raw("blob".to_string(), vec![0xff, 0x00]).unwrap();
+        assert!(!db.roundtrip_ok());
+        assert_eq!(db.roundtrip_mismatches().unwrap(), vec![
+            ("jsonl", "blob".to_string()),
+            ("csv", "blob".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,62 @@
+//! Pseudo-random sampling of documents
+
+use crate::error::Result;
+use crate::rng::Rng;
+use crate::BurrowDB;
+
+impl BurrowDB {
+    /// Up to `n` distinct documents chosen pseudo-randomly from both tiers
+    ///
+    /// The choice depends only on `seed` and the set of keys, so the same
+    /// seed over the same data always returns the same sample, in the same
+    /// order. Only the chosen values are read, without promoting anything.
+    pub fn sample(&self, n: usize, seed: u64) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut keys = self.keys()?;
+        keys.sort_unstable();
+
+        // Partial Fisher-Yates: the first `n` slots end up a uniform sample
+        let n = n.min(keys.len());
+        let mut rng = Rng::new(seed);
+        for i in 0..n {
+            let j = i + rng.below(keys.len() - i);
+            keys.swap(i, j);
+        }
+
+        let mut sample = Vec::with_capacity(n);
+        for key in keys.into_iter().take(n) {
+            if let Some(value) = self.peek(&key)? {
+                sample.push((key, value));
+            }
+        }
+        Ok(sample)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sample_is_deterministic_and_distinct() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 5).unwrap();
+        for i in 0..20 {
+            db.put_raw(format!("key-{}", i), i.to_string().into_bytes()).unwrap();
+        }
+
+        let first = db.sample(8, 7).unwrap();
+        assert_eq!(first.len(), 8);
+        assert_eq!(first, db.sample(8, 7).unwrap());
+        let mut keys: Vec<&str> = first.iter().map(|(key, _)| key.as_str()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), 8);
+        for (key, value) in &first {
+            assert_eq!(key.strip_prefix("key-").unwrap().as_bytes(), &value[..]);
+        }
+
+        assert_eq!(db.sample(100, 1).unwrap().len(), 20);
+        assert!(db.sample(0, 1).unwrap().is_empty());
+    }
+}
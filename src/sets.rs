@@ -0,0 +1,69 @@
+//! Set values stored under a key
+//!
+//! Sets live in their own in-memory namespace, separate from documents, so
+//! a key can name both a document and a set without either affecting the
+//! other. Like links, sets are not written to the cold tier.
+
+use crate::BurrowDB;
+
+impl BurrowDB {
+    /// Add `member` to the set at `key`, creating the set if needed
+    ///
+    /// Returns whether the member was newly added.
+    pub fn sadd(&mut self, key: &str, member: &str) -> bool {
+        let key = self.normalize_owned_key(key.to_string());
+        self.sets.entry(key).or_default().insert(member.to_string())
+    }
+
+    /// Members of the set at `key`, sorted (empty if there is no set)
+    pub fn smembers(&self, key: &str) -> Vec<&str> {
+        let mut members: Vec<&str> = self
+            .sets
+            .get(&*self.normalize_key(key))
+            .map(|set| set.iter().map(String::as_str).collect())
+            .unwrap_or_default();
+        members.sort_unstable();
+        members
+    }
+
+    /// Remove `member` from the set at `key`
+    ///
+    /// Returns whether it was present. A set is dropped once its last member
+    /// is removed.
+    pub fn srem(&mut self, key: &str, member: &str) -> bool {
+        let key = &*self.normalize_key(key);
+        let Some(set) = self.sets.get_mut(key) else {
+            return false;
+        };
+
+        let removed = set.remove(member);
+        if set.is_empty() {
+            self.sets.remove(key);
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_set_operations() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.put_raw("tags".to_string(), b"a document".to_vec()).unwrap();
+
+        assert!(db.sadd("tags", "rust"));
+        assert!(db.sadd("tags", "db"));
+        assert!(!db.sadd("tags", "rust"));
+        assert_eq!(db.smembers("tags"), vec!["db", "rust"]);
+        assert_eq!(db.get("tags").unwrap().unwrap(), b"a document");
+
+        assert!(db.srem("tags", "rust"));
+        assert!(!db.srem("tags", "rust"));
+        assert!(db.srem("tags", "db"));
+        assert!(db.smembers("tags").is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+//! Reading values as settings
+//!
+//! For applications that keep their configuration in the store. Values are
+//! still untyped text; these parse it on the way out, by the same rules
+//! `type_histogram` uses to classify values. Like `peek`, they never count
+//! a read or promote, and an expired key reads as missing.
+
+use crate::error::Result;
+use crate::BurrowDB;
+
+impl BurrowDB {
+    /// `key` as a boolean: `true` or `false` once trimmed
+    ///
+    /// `None` if the key is missing or holds anything else.
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>> {
+        Ok(self.setting(key)?.and_then(|text| text.trim().parse().ok()))
+    }
+
+    /// `key` as an `i64`, or `None` if missing or not an integer
+    pub fn get_i64(&self, key: &str) -> Result<Option<i64>> {
+        Ok(self.setting(key)?.and_then(|text| text.trim().parse().ok()))
+    }
+
+    /// `key` as a finite `f64`, or `None` if missing or not a number
+    ///
+    /// Integers are read as floats too.
+    pub fn get_f64(&self, key: &str) -> Result<Option<f64>> {
+        let number = self.setting(key)?.and_then(|text| text.trim().parse::<f64>().ok());
+        Ok(number.filter(|n| n.is_finite()))
+    }
+
+    /// Every document whose key starts with `prefix`, sorted by key
+    ///
+    /// The prefix is matched literally, unlike a `keys_matching` pattern.
+    /// Expired keys are left out.
+    pub fn entries_with_prefix(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        let prefix = &*self.normalize_key(prefix);
+        let mut entries = Vec::new();
+        self.for_each_entry(|key, value| {
+            if key.starts_with(prefix) && !self.is_expired(key) {
+                entries.push((key.to_string(), value.to_vec()));
+            }
+            Ok(())
+        })?;
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+
+    /// The value at `key` as text, if it exists, hasn't expired and is UTF-8
+    fn setting(&self, key: &str) -> Result<Option<String>> {
+        let key = &*self.normalize_key(key);
+        if self.is_expired(key) {
+            return Ok(None);
+        }
+        Ok(self.peek(key)?.and_then(|value| String::from_utf8(value).ok()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_typed_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        for (key, value) in [
+            ("app.debug", " true "),
+            ("app.retries", "3"),
+            ("app.ratio", "0.25"),
+            ("app.name", "burrow"),
+            ("app.old", "1"),
+            ("other", "x"),
+        ] {
+            db.put_raw(key.to_string(), value.as_bytes().to_vec()).unwrap();
+        }
+        db.demote("app.retries").unwrap();
+        db.expire("app.old", Duration::ZERO);
+
+        assert_eq!(db.get_bool("app.debug").unwrap(), Some(true));
+        assert_eq!(db.get_bool("app.retries").unwrap(), None);
+        assert_eq!(db.get_i64("app.retries").unwrap(), Some(3));
+        assert_eq!(db.get_i64("app.ratio").unwrap(), None);
+        assert_eq!(db.get_f64("app.ratio").unwrap(), Some(0.25));
+        assert_eq!(db.get_f64("app.retries").unwrap(), Some(3.0));
+        assert_eq!(db.get_f64("app.name").unwrap(), None);
+        assert_eq!(db.get_i64("app.old").unwrap(), None);
+        assert_eq!(db.get_bool("missing").unwrap(), None);
+
+        let keys: Vec<String> = db
+            .entries_with_prefix("app.")
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, vec!["app.debug", "app.name", "app.ratio", "app.retries"]);
+    }
+}
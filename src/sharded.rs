@@ -0,0 +1,102 @@
+//! Hash-partitioned database for experimenting with sharded layouts
+//!
+//! Each shard is an independent `BurrowDB` with its own lock and its own
+//! subdirectory, so operations on keys in different shards never contend.
+
+use crate::checksum::fnv1a64;
+use crate::error::{BurrowError, Result};
+use crate::BurrowDB;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A set of `BurrowDB` shards, each key living in exactly one
+///
+/// Keys are assigned with FNV-1a, which is stable across runs and Rust
+/// versions, so reopening with the same shard count finds every key again.
+pub struct ShardedBurrowDB {
+    shards: Vec<Mutex<BurrowDB>>,
+}
+
+impl ShardedBurrowDB {
+    /// Open `shards` databases under `data_dir` (as `shard-0`, `shard-1`, ...)
+    pub fn new(data_dir: &str, shards: usize) -> Result<Self> {
+        if shards == 0 {
+            return Err(BurrowError::InvalidConfig(
+                "a sharded database needs at least one shard".to_string(),
+            ));
+        }
+
+        let shards = (0..shards)
+            .map(|i| {
+                let dir = Path::new(data_dir).join(format!("shard-{}", i));
+                let db = BurrowDB::builder().data_dir(&dir.to_string_lossy()).build()?;
+                Ok(Mutex::new(db))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { shards })
+    }
+
+    /// Store raw bytes in the key's shard
+    pub fn put(&self, key: String, data: Vec<u8>) -> Result<()> {
+        self.shard_for(&key).put_raw(key, data)
+    }
+
+    /// Retrieve a document from the key's shard
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.shard_for(key).get(key)
+    }
+
+    /// Delete a document from the key's shard
+    pub fn delete(&self, key: &str) -> Result<()> {
+        self.shard_for(key).delete(key)
+    }
+
+    /// Number of shards
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Keys held by each shard, in shard order, for checking balance
+    pub fn shard_key_counts(&self) -> Result<Vec<usize>> {
+        self.shards
+            .iter()
+            .map(|shard| Ok(lock(shard).keys()?.len()))
+            .collect()
+    }
+
+    fn shard_for(&self, key: &str) -> MutexGuard<'_, BurrowDB> {
+        let index = fnv1a64(key.as_bytes()) % self.shards.len() as u64;
+        lock(&self.shards[index as usize])
+    }
+}
+
+/// Lock a shard, recovering it if another thread panicked while holding it
+fn lock(shard: &Mutex<BurrowDB>) -> MutexGuard<'_, BurrowDB> {
+    shard.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_keys_spread_across_shards() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = ShardedBurrowDB::new(temp_dir.path().to_str().unwrap(), 4).unwrap();
+
+        for i in 0..100 {
+            db.put(format!("key-{}", i), i.to_string().into_bytes()).unwrap();
+        }
+        assert_eq!(db.get("key-42").unwrap().unwrap(), b"42");
+        db.delete("key-42").unwrap();
+        assert_eq!(db.get("key-42").unwrap(), None);
+
+        let counts = db.shard_key_counts().unwrap();
+        assert_eq!(counts.iter().sum::<usize>(), 99);
+        assert!(counts.iter().all(|&count| count > 0));
+
+        assert!(ShardedBurrowDB::new(temp_dir.path().to_str().unwrap(), 0).is_err());
+    }
+}
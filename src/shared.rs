@@ -0,0 +1,169 @@
+//! Thread-safe handle for sharing one database between threads
+//!
+//! `BurrowDB` needs `&mut self` even for reads (they promote documents and
+//! count accesses), so sharing uses a `Mutex` rather than an `RwLock`.
+
+use crate::error::{BurrowError, Result};
+use crate::BurrowDB;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A `BurrowDB` behind a lock, cheap to clone across threads
+///
+/// ```no_run
+/// use burrow_db::{BurrowDB, SharedBurrowDB};
+/// use std::time::Duration;
+///
+/// let shared = SharedBurrowDB::new(BurrowDB::new()?);
+/// shared.enable_autosave("backup.txt", Duration::from_secs(60));
+/// shared.lock().put_raw("k".to_string(), b"v".to_vec())?;
+/// shared.disable_autosave()?;
+/// # Ok::<(), burrow_db::BurrowError>(())
+/// ```
+#[derive(Clone)]
+pub struct SharedBurrowDB {
+    db: Arc<Mutex<BurrowDB>>,
+    autosave: Arc<Mutex<Option<Autosave>>>,
+}
+
+impl SharedBurrowDB {
+    pub fn new(db: BurrowDB) -> Self {
+        Self {
+            db: Arc::new(Mutex::new(db)),
+            autosave: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Lock the database for exclusive use
+    ///
+    /// A panic while the lock was held doesn't make the data unusable, so a
+    /// poisoned lock is recovered rather than propagated.
+    pub fn lock(&self) -> MutexGuard<'_, BurrowDB> {
+        self.db.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Save the database to `path` with `save_to_file` every `every`
+    ///
+    /// Each save holds the lock while the file is written and replaces the
+    /// file atomically, so readers of `path` never see a partial save. A
+    /// failed save is retried on the next tick and reported by
+    /// `disable_autosave`. Enabling again replaces the previous schedule.
+    pub fn enable_autosave(&self, path: &str, every: Duration) {
+        let db = Arc::clone(&self.db);
+        let path = PathBuf::from(path);
+        let (stop, ticks) = mpsc::channel();
+
+        let thread = thread::spawn(move || {
+            let mut last_error = None;
+            while let Err(RecvTimeoutError::Timeout) = ticks.recv_timeout(every) {
+                let db = db.lock().unwrap_or_else(PoisonError::into_inner);
+                last_error = db.save_to_file(&path).err();
+            }
+            last_error
+        });
+
+        // Join any previous thread after releasing the slot lock
+        let previous = self.autosave_slot().replace(Autosave { stop, thread: Some(thread) });
+        drop(previous);
+    }
+
+    /// Stop autosaving, waiting for any save in progress to finish
+    ///
+    /// Returns the error from the most recent save if it failed. Does
+    /// nothing if autosave isn't enabled.
+    pub fn disable_autosave(&self) -> Result<()> {
+        match self.autosave_slot().take() {
+            Some(mut autosave) => autosave.stop().map_or(Ok(()), Err),
+            None => Ok(()),
+        }
+    }
+
+    /// Add `by` to the integer at `key` as `BurrowDB::incr` does
+    ///
+    /// The read and the write happen under one lock, so concurrent
+    /// increments never lose an update.
+    pub fn incr(&self, key: &str, by: i64) -> Result<i64> {
+        self.lock().incr(key, by)
+    }
+
+    fn autosave_slot(&self) -> MutexGuard<'_, Option<Autosave>> {
+        self.autosave.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A running autosave thread, stopped when dropped
+struct Autosave {
+    stop: Sender<()>,
+    thread: Option<JoinHandle<Option<BurrowError>>>,
+}
+
+impl Autosave {
+    /// Signal the thread and join it, returning its last save error
+    fn stop(&mut self) -> Option<BurrowError> {
+        let _ = self.stop.send(());
+        let thread = self.thread.take()?;
+        thread.join().unwrap_or_else(|_| {
+            Some(BurrowError::StorageError("autosave thread panicked".to_string()))
+        })
+    }
+}
+
+impl Drop for Autosave {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_autosave_writes_loadable_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("auto.txt");
+        let db = BurrowDB::with_config(temp_dir.path().join("db").to_str().unwrap(), 100).unwrap();
+
+        let shared = SharedBurrowDB::new(db);
+        shared.lock().put_raw("k".to_string(), b"v".to_vec()).unwrap();
+        shared.enable_autosave(file.to_str().unwrap(), Duration::from_millis(10));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !file.exists() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        shared.disable_autosave().unwrap();
+
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("r").to_str().unwrap(), 100).unwrap();
+        assert_eq!(restored.load_from_file(&file).unwrap(), 1);
+        assert_eq!(restored.get("k").unwrap().unwrap(), b"v");
+    }
+
+    #[test]
+    fn test_concurrent_incr_loses_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        let shared = SharedBurrowDB::new(db);
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    for _ in 0..500 {
+                        shared.incr("counter", 3).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(shared.incr("counter", 0).unwrap(), 8 * 500 * 3);
+    }
+}
@@ -1,8 +1,10 @@
 use crate::document_block::DocumentBlock;
 use crate::error::{BurrowError, Result};
 use std::fs::{self, File};
+use std::io::ErrorKind;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Storage manager for cold tier (disk-based) document blocks
 pub struct Storage {
@@ -22,6 +24,19 @@ impl Storage {
         Ok(Self { data_dir })
     }
     
+    /// Directory holding the cold tier files
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Check that new files can be created in the data directory
+    pub fn check_writable(&self) -> Result<()> {
+        let probe = self.data_dir.join(".write-check");
+        File::create(&probe)?.write_all(b"ok")?;
+        fs::remove_file(&probe)?;
+        Ok(())
+    }
+
     /// Save a document block to disk
     pub fn save(&self, key: &str, block: &DocumentBlock) -> Result<()> {
         let file_path = self.get_file_path(key);
@@ -33,12 +48,12 @@ impl Storage {
             }
         }
         
-        // Write the FlatBuffer bytes to disk
+        // Write the FlatBuffer bytes to disk; cold files are never compressed
         let mut file = File::create(&file_path)?;
-        file.write_all(block.as_bytes())?;
+        file.write_all(&block.value())?;
         file.sync_all()?;
-        
-        Ok(())
+
+        self.write_updated_at(key, block.updated_at)
     }
     
     /// Load a document block from disk
@@ -54,8 +69,53 @@ impl Storage {
         let mut data = Vec::new();
         file.read_to_end(&mut data)?;
         
-        // Create DocumentBlock from bytes
-        DocumentBlock::new(data)
+        // Cold blocks may hold raw values written via `put_raw`, so they are
+        // not re-parsed as FlatBuffers here
+        let mut block = DocumentBlock::from_raw(data);
+        block.updated_at = self.updated_at(key)?;
+
+        Ok(block)
+    }
+
+    /// When a block's value was last changed or touched (Unix seconds),
+    /// without reading it
+    ///
+    /// The time is kept in a `.meta` file beside the block, so moving a
+    /// document between tiers doesn't change it. Blocks written before
+    /// those files existed fall back to their file's modification time.
+    pub fn updated_at(&self, key: &str) -> Result<u64> {
+        let file_path = self.get_file_path(key);
+
+        if !file_path.exists() {
+            return Err(BurrowError::KeyNotFound(key.to_string()));
+        }
+
+        match fs::read_to_string(self.get_meta_path(key)) {
+            Ok(text) => text.trim().parse().map_err(|_| {
+                BurrowError::StorageError(format!("malformed update time for {}", key))
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let modified = fs::metadata(&file_path)?.modified()?;
+                Ok(modified.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Mark a block as updated now without rewriting it
+    pub fn touch(&self, key: &str) -> Result<()> {
+        if !self.exists(key) {
+            return Err(BurrowError::KeyNotFound(key.to_string()));
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        self.write_updated_at(key, now)
+    }
+
+    /// Record a block's update time in its `.meta` file
+    fn write_updated_at(&self, key: &str, updated_at: u64) -> Result<()> {
+        fs::write(self.get_meta_path(key), updated_at.to_string())?;
+        Ok(())
     }
     
     /// Delete a document block from disk
@@ -67,7 +127,10 @@ impl Storage {
         }
         
         fs::remove_file(&file_path)?;
-        Ok(())
+        match fs::remove_file(self.get_meta_path(key)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
     }
     
     /// Check if a key exists on disk
@@ -89,9 +152,12 @@ impl Storage {
     
     /// Get the file path for a given key
     fn get_file_path(&self, key: &str) -> PathBuf {
-        // Sanitize the key to create a safe filename
-        let safe_key = key.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
-        self.data_dir.join(format!("{}.block", safe_key))
+        self.data_dir.join(format!("{}.block", encode_file_name(key)))
+    }
+
+    /// Get the path of the file holding a block's update time
+    fn get_meta_path(&self, key: &str) -> PathBuf {
+        self.data_dir.join(format!("{}.meta", encode_file_name(key)))
     }
     
     /// Recursively collect keys from the data directory
@@ -103,8 +169,8 @@ impl Storage {
             if path.is_file() {
                 if let Some(file_name) = path.file_name() {
                     if let Some(name_str) = file_name.to_str() {
-                        if name_str.ends_with(".block") {
-                            let key = name_str.trim_end_matches(".block");
+                        if let Some(name) = name_str.strip_suffix(".block") {
+                            let key = decode_file_name(name);
                             let full_key = if prefix.is_empty() {
                                 key.to_string()
                             } else {
@@ -160,5 +226,75 @@ impl Storage {
     }
 }
 
+/// Characters that aren't allowed in file names on every platform, plus
+/// `%`, which starts an escape
+const RESERVED: [char; 10] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|', '%'];
 
+/// The file name (without extension) a key is stored under
+///
+/// Reserved and control characters are written as `%XX`, so distinct keys
+/// never share a file and `decode_file_name` recovers the key exactly.
+fn encode_file_name(key: &str) -> String {
+    let mut name = String::with_capacity(key.len());
+    for c in key.chars() {
+        if RESERVED.contains(&c) || c.is_ascii_control() {
+            name.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
 
+/// Reverse `encode_file_name`
+///
+/// A `%` not followed by two hex digits is kept as written, so files
+/// named before keys were encoded still list under their file name.
+fn decode_file_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut key = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| name.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                key.push(byte);
+                i += 3;
+            }
+            None => {
+                key.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&key).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_names_are_reversible() {
+        for key in ["user:1", "user_1", "a/b\\c", "100%", "%41", "tab\there", "é🦀?", ""] {
+            let name = encode_file_name(key);
+            assert!(!name.contains(['/', '\\', ':', '\t']), "{:?} -> {:?}", key, name);
+            assert_eq!(decode_file_name(&name), key);
+        }
+        assert_eq!(decode_file_name("50%_off"), "50%_off");
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path()).unwrap();
+        for key in ["user:1", "user_1", "docs/readme"] {
+            storage.save(key, &DocumentBlock::from_raw(key.as_bytes().to_vec())).unwrap();
+        }
+        let mut keys = storage.list_keys().unwrap();
+        keys.sort();
+        assert_eq!(keys, ["docs/readme", "user:1", "user_1"]);
+        assert_eq!(storage.load("user:1").unwrap().as_bytes(), b"user:1");
+    }
+}
@@ -0,0 +1,225 @@
+//! Labels attached to keys
+//!
+//! Like links, tags live in memory beside the documents and are dropped
+//! when their key is deleted. Both directions are indexed, so finding a
+//! key's tags and finding a tag's keys are each a single lookup.
+
+use crate::error::{BurrowError, Result};
+use crate::BurrowDB;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Default)]
+pub(crate) struct TagIndex {
+    /// tag → keys carrying it
+    by_tag: HashMap<String, HashSet<String>>,
+    /// key → its tags
+    by_key: HashMap<String, HashSet<String>>,
+}
+
+impl TagIndex {
+    /// Tag `key`, returning whether the tag is new for it
+    pub(crate) fn add(&mut self, key: &str, tag: &str) -> bool {
+        let added = self.by_key.entry(key.to_string()).or_default().insert(tag.to_string());
+        self.by_tag.entry(tag.to_string()).or_default().insert(key.to_string());
+        added
+    }
+
+    /// Remove one tag from `key`, returning whether it was there
+    pub(crate) fn remove(&mut self, key: &str, tag: &str) -> bool {
+        let removed = remove_pair(&mut self.by_key, key, tag);
+        remove_pair(&mut self.by_tag, tag, key);
+        removed
+    }
+
+    /// Drop every tag on `key`
+    pub(crate) fn remove_key(&mut self, key: &str) {
+        for tag in self.by_key.remove(key).unwrap_or_default() {
+            remove_pair(&mut self.by_tag, &tag, key);
+        }
+    }
+
+    pub(crate) fn has(&self, key: &str, tag: &str) -> bool {
+        self.by_key.get(key).is_some_and(|tags| tags.contains(tag))
+    }
+
+    /// Keys carrying `tag`, sorted
+    pub(crate) fn keys_with(&self, tag: &str) -> Vec<&str> {
+        sorted(self.by_tag.get(tag))
+    }
+
+    /// Tags on `key`, sorted
+    pub(crate) fn tags_of(&self, key: &str) -> Vec<&str> {
+        sorted(self.by_key.get(key))
+    }
+
+    /// Every tag with the number of keys carrying it, most used first
+    ///
+    /// Ties are broken by tag name so the order is stable.
+    pub(crate) fn counts(&self) -> Vec<(&str, usize)> {
+        let mut counts: Vec<(&str, usize)> =
+            self.by_tag.iter().map(|(tag, keys)| (tag.as_str(), keys.len())).collect();
+        counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+
+    /// Whether `key` carries any tag
+    pub(crate) fn is_tagged(&self, key: &str) -> bool {
+        self.by_key.contains_key(key)
+    }
+
+    /// Every tagged key
+    pub(crate) fn tagged_keys(&self) -> impl Iterator<Item = &str> {
+        self.by_key.keys().map(String::as_str)
+    }
+
+    /// Whether both maps describe the same (key, tag) pairs
+    pub(crate) fn is_consistent(&self) -> bool {
+        let pairs: usize = self.by_key.values().map(HashSet::len).sum();
+        let reverse: usize = self.by_tag.values().map(HashSet::len).sum();
+        pairs == reverse
+            && self.by_key.iter().all(|(key, tags)| {
+                tags.iter().all(|tag| self.by_tag.get(tag).is_some_and(|keys| keys.contains(key)))
+            })
+    }
+}
+
+/// Remove `value` from the set at `map[key]`, dropping the set once empty
+fn remove_pair(map: &mut HashMap<String, HashSet<String>>, key: &str, value: &str) -> bool {
+    let Some(set) = map.get_mut(key) else {
+        return false;
+    };
+    let removed = set.remove(value);
+    if set.is_empty() {
+        map.remove(key);
+    }
+    removed
+}
+
+fn sorted(set: Option<&HashSet<String>>) -> Vec<&str> {
+    let mut items: Vec<&str> = set
+        .map(|set| set.iter().map(String::as_str).collect())
+        .unwrap_or_default();
+    items.sort_unstable();
+    items
+}
+
+impl BurrowDB {
+    /// Attach `tag` to an existing key
+    ///
+    /// Returns whether the key didn't already carry the tag, or `KeyNotFound`.
+    pub fn tag(&mut self, key: &str, tag: &str) -> Result<bool> {
+        let key = &*self.normalize_key(key).into_owned();
+        if !self.contains_key(key) {
+            return Err(BurrowError::KeyNotFound(key.to_string()));
+        }
+        self.settle_index_drop(key);
+        Ok(self.tags.add(key, tag))
+    }
+
+    /// Remove `tag` from a key, returning whether it was there
+    pub fn untag(&mut self, key: &str, tag: &str) -> bool {
+        let key = &*self.normalize_key(key);
+        self.tags.remove(key, tag)
+    }
+
+    /// Whether `key` carries `tag`
+    pub fn has_tag(&self, key: &str, tag: &str) -> bool {
+        self.tags.has(&self.normalize_key(key), tag)
+    }
+
+    /// Keys carrying `tag`, sorted
+    pub fn keys_with_tag(&self, tag: &str) -> Vec<&str> {
+        self.tags.keys_with(tag)
+    }
+
+    /// Tags on `key`, sorted
+    pub fn tags_of(&self, key: &str) -> Vec<&str> {
+        self.tags.tags_of(&self.normalize_key(key))
+    }
+
+    /// Every tag in use with how many keys carry it, most used first
+    ///
+    /// Tags used equally often are sorted by name.
+    pub fn all_tags(&self) -> Vec<(&str, usize)> {
+        self.tags.counts()
+    }
+
+    /// Keys carrying no tags at all, sorted
+    ///
+    /// Expired keys that haven't been removed yet are left out.
+    pub fn untagged_keys(&self) -> Result<Vec<String>> {
+        let mut keys: Vec<String> = self
+            .keys()?
+            .into_iter()
+            .filter(|key| !self.tags.is_tagged(key) && !self.is_expired(key))
+            .collect();
+        keys.sort_unstable();
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tags_follow_their_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.put_raw("a".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("b".to_string(), b"2".to_vec()).unwrap();
+
+        assert!(db.tag("a", "red").unwrap());
+        assert!(!db.tag("a", "red").unwrap());
+        db.tag("b", "red").unwrap();
+        db.tag("a", "big").unwrap();
+        assert!(matches!(db.tag("missing", "red"), Err(BurrowError::KeyNotFound(_))));
+
+        assert_eq!(db.keys_with_tag("red"), vec!["a", "b"]);
+        assert_eq!(db.tags_of("a"), vec!["big", "red"]);
+
+        db.delete("a").unwrap();
+        assert_eq!(db.keys_with_tag("red"), vec!["b"]);
+        assert!(db.keys_with_tag("big").is_empty());
+        assert!(db.untag("b", "red"));
+        assert!(db.tags.is_consistent());
+    }
+
+    #[test]
+    fn test_untagged_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        for key in ["c", "a", "b", "gone"] {
+            db.put_raw(key.to_string(), b"1".to_vec()).unwrap();
+        }
+        db.tag("b", "red").unwrap();
+        db.demote("c").unwrap();
+        db.expire("gone", std::time::Duration::ZERO);
+
+        assert_eq!(db.untagged_keys().unwrap(), vec!["a", "c"]);
+        db.untag("b", "red");
+        assert_eq!(db.untagged_keys().unwrap(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_all_tags_counts_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        for key in ["a", "b", "c"] {
+            db.put_raw(key.to_string(), b"1".to_vec()).unwrap();
+            db.tag(key, "important").unwrap();
+        }
+        db.tag("a", "red").unwrap();
+        db.tag("b", "improtant").unwrap();
+        db.tag("c", "blue").unwrap();
+
+        assert_eq!(
+            db.all_tags(),
+            vec![("important", 3), ("blue", 1), ("improtant", 1), ("red", 1)]
+        );
+
+        db.delete("a").unwrap();
+        assert_eq!(db.all_tags(), vec![("important", 2), ("blue", 1), ("improtant", 1)]);
+    }
+}
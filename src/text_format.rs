@@ -0,0 +1,868 @@
+//! Plain-text save format
+//!
+//! Each document is written as one `key<TAB>value` line. Backslashes, tabs,
+//! newlines and other control characters are escaped, and values that are
+//! not valid UTF-8 have their non-ASCII bytes written as `\xHH`, so any
+//! binary value survives a save/load round trip.
+//!
+//! Lines starting with `#` are comments and blank lines are ignored, so
+//! saved files can be annotated by hand. Keys starting with `#` are written
+//! as `\#` so they are never mistaken for a comment.
+//!
+//! Tags and links are written after the records as `#@tag key<TAB>tag` and
+//! `#@link from<TAB>rel<TAB>to` lines, escaped like records. Being comments,
+//! they are ignored by anything that only reads documents.
+//!
+//! The last line is a `#checksum <hex>` trailer holding the FNV-1a hash of
+//! every line before it.
+//!
+//! `TextFormat` can swap the tab and newline for other separators, such as
+//! `\0`-terminated records. A separator that is otherwise printable is
+//! written as `\xHH` wherever it occurs in a key, value, tag or relation.
+
+use crate::checksum::{fnv1a64, fnv1a64_extend, FNV1A64_START};
+use crate::error::{BurrowError, Result};
+use crate::BurrowDB;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CHECKSUM_PREFIX: &str = "#checksum ";
+const TAG_PREFIX: &str = "#@tag ";
+const LINK_PREFIX: &str = "#@link ";
+
+/// Options for writing and reading the text save format
+///
+/// ```no_run
+/// use burrow_db::{BurrowDB, TextFormat};
+///
+/// let mut db = BurrowDB::new()?;
+/// let format = TextFormat { record_separator: '\0', ..TextFormat::default() };
+/// db.save_to_file_with("data.txt", &format)?;
+/// db.load_from_file_with("data.txt", &format)?;
+/// # Ok::<(), burrow_db::BurrowError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextFormat {
+    /// Start the file with a `# generated at <unix time>` comment
+    pub header: bool,
+    /// Character between a key and its value (default tab)
+    pub field_separator: char,
+    /// Character ending each record (default `\n`)
+    pub record_separator: char,
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        Self {
+            header: false,
+            field_separator: '\t',
+            record_separator: '\n',
+        }
+    }
+}
+
+impl TextFormat {
+    /// Check that the separators can be escaped unambiguously
+    ///
+    /// Each must be a distinct ASCII character other than a letter, digit,
+    /// `\` or `#`, since those appear in escapes and comments. Returns
+    /// `InvalidConfig` otherwise.
+    pub fn validate(&self) -> Result<()> {
+        for (name, sep) in [("field", self.field_separator), ("record", self.record_separator)] {
+            if !sep.is_ascii() || sep.is_ascii_alphanumeric() || sep == '\\' || sep == '#' {
+                return Err(BurrowError::InvalidConfig(format!(
+                    "{} separator {:?} can't be escaped; use ASCII punctuation or a \
+                     control character",
+                    name, sep
+                )));
+            }
+        }
+        if self.field_separator == self.record_separator {
+            return Err(BurrowError::InvalidConfig(
+                "field and record separators must differ".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl BurrowDB {
+    /// Save every document from both tiers to a text file
+    ///
+    /// Keys are written in sorted order so saves of the same data are identical.
+    /// The file is replaced atomically, so a crash mid-save leaves the
+    /// previous version intact.
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.save_to_file_with(path, &TextFormat::default())
+    }
+
+    /// Save to a text file using the given format options
+    ///
+    /// Returns `InvalidConfig` if the separators fail `TextFormat::validate`.
+    pub fn save_to_file_with<P: AsRef<Path>>(&self, path: P, format: &TextFormat) -> Result<()> {
+        format.validate()?;
+        write_atomic(path.as_ref(), self.encode_text(format)?.as_bytes())
+    }
+
+    /// Every document, sorted by key, in the text format
+    pub(crate) fn encode_text(&self, format: &TextFormat) -> Result<String> {
+        let mut entries = self.entries()?;
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut contents = String::new();
+        if format.header {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            contents.push_str(&format!("# generated at {} (unix time)", now));
+            contents.push(format.record_separator);
+        }
+
+        for (key, value) in &entries {
+            contents.push_str(&escape_key(key, format));
+            contents.push(format.field_separator);
+            contents.push_str(&escape(value, format));
+            contents.push(format.record_separator);
+        }
+        self.encode_indexes(&mut contents, format);
+
+        let checksum = fnv1a64(contents.as_bytes());
+        contents.push_str(&format!("{}{:016x}", CHECKSUM_PREFIX, checksum));
+        contents.push(format.record_separator);
+        Ok(contents)
+    }
+
+    /// Load documents from a text file written by `save_to_file`
+    ///
+    /// Comment lines (starting with `#`) and blank lines are skipped. The
+    /// checksum trailer is verified before anything is loaded, returning
+    /// `ChecksumMismatch` if the file was damaged. Files without a trailer
+    /// (written before checksums were added) load unchecked.
+    ///
+    /// Loaded documents are stored as raw values, overwriting existing keys.
+    /// Saved tags and links are then restored alongside any already present.
+    /// Returns the number of documents loaded.
+    ///
+    /// The file is streamed line by line rather than read whole, once to
+    /// verify the checksum and once to load, so its size isn't limited by
+    /// memory.
+    pub fn load_from_file<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        self.load_text_file(path.as_ref(), &TextFormat::default(), true, 0, |_| {})
+    }
+
+    /// Load a file written by `save_to_file_with` using the same separators
+    ///
+    /// A value holding an unescaped field separator is rejected, since
+    /// the file was then not written with this format.
+    pub fn load_from_file_with<P>(&mut self, path: P, format: &TextFormat) -> Result<usize>
+    where
+        P: AsRef<Path>,
+    {
+        format.validate()?;
+        self.load_text_file(path.as_ref(), format, true, 0, |_| {})
+    }
+
+    /// Load like `load_from_file`, calling `progress` with the running
+    /// count after every `every` documents
+    ///
+    /// Gives feedback during long imports. An `every` of 0 never calls it.
+    pub fn load_from_file_with_progress<P, F>(&mut self, path: P, every: usize, progress: F) -> Result<usize>
+    where
+        P: AsRef<Path>,
+        F: FnMut(usize),
+    {
+        self.load_text_file(path.as_ref(), &TextFormat::default(), true, every, progress)
+    }
+
+    /// Load a text file without verifying its checksum
+    ///
+    /// Use this to recover what is still readable from a damaged file.
+    pub fn load_from_file_unchecked<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        self.load_text_file(path.as_ref(), &TextFormat::default(), false, 0, |_| {})
+    }
+
+    fn load_text_file<F>(
+        &mut self,
+        path: &Path,
+        format: &TextFormat,
+        verify: bool,
+        every: usize,
+        mut progress: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(usize),
+    {
+        if verify {
+            verify_file_checksum(path, format)?;
+        }
+
+        // Tags and links are applied once every document is in
+        let mut indexes = Vec::new();
+        let mut loaded = 0;
+        for (index, line) in file_records(path, format)?.enumerate() {
+            let line = line?;
+            if line.starts_with(TAG_PREFIX) || line.starts_with(LINK_PREFIX) {
+                indexes.push((index, line));
+                continue;
+            }
+            if is_comment_or_blank(&line, format) {
+                continue;
+            }
+
+            let (key, value) = parse_line(&line, format).map_err(|msg| {
+                BurrowError::SerializationError(format!("line {}: {}", index + 1, msg))
+            })?;
+            self.put_raw(key, value)?;
+            loaded += 1;
+            if every > 0 && loaded % every == 0 {
+                progress(loaded);
+            }
+        }
+
+        for (index, line) in indexes {
+            self.restore_index_line(index, &line, format)?;
+        }
+        Ok(loaded)
+    }
+
+    /// Check that a text file would load cleanly, without loading it
+    ///
+    /// The file is streamed: the checksum is verified, then every record,
+    /// `#@tag` and `#@link` line is parsed, and tags and links must name
+    /// keys the file contains. Returns how many distinct keys loading it
+    /// would store. Every malformed line is listed in one
+    /// `SerializationError`, not just the first.
+    pub fn verify_save_file<P: AsRef<Path>>(path: P) -> Result<usize> {
+        Self::verify_save_file_with(path, &TextFormat::default())
+    }
+
+    /// Check a file written by `save_to_file_with`, as `verify_save_file` does
+    pub fn verify_save_file_with<P: AsRef<Path>>(path: P, format: &TextFormat) -> Result<usize> {
+        format.validate()?;
+        let path = path.as_ref();
+        verify_file_checksum(path, format)?;
+
+        let mut keys = HashSet::new();
+        // Keys each tag or link line names, checked once every record is seen
+        let mut named = Vec::new();
+        let mut problems = Vec::new();
+        for (index, line) in file_records(path, format)?.enumerate() {
+            let line = line?;
+            let parsed = if let Some(rest) = line.strip_prefix(TAG_PREFIX) {
+                parse_fields(rest, 2, format)
+                    .map(|mut fields| named.push((index, fields.swap_remove(0))))
+            } else if let Some(rest) = line.strip_prefix(LINK_PREFIX) {
+                parse_fields(rest, 3, format).map(|mut fields| {
+                    named.push((index, fields.swap_remove(2)));
+                    named.push((index, fields.swap_remove(0)));
+                })
+            } else if is_comment_or_blank(&line, format) {
+                Ok(())
+            } else {
+                parse_line(&line, format).map(|(key, _)| {
+                    keys.insert(key);
+                })
+            };
+
+            if let Err(msg) = parsed {
+                problems.push((index, msg));
+            }
+        }
+
+        for (index, key) in named {
+            if !keys.contains(&key) {
+                problems.push((index, format!("names missing key '{}'", key)));
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(keys.len());
+        }
+        problems.sort_by_key(|(index, _)| *index);
+        let problems: Vec<String> = problems
+            .into_iter()
+            .map(|(index, msg)| format!("line {}: {}", index + 1, msg))
+            .collect();
+        Err(BurrowError::SerializationError(problems.join("; ")))
+    }
+
+    /// Append a `#@tag` line per tag and a `#@link` line per link, sorted
+    ///
+    /// Entries still held for keys deleted under `defer_indexing` are left
+    /// out, since they would name keys the file doesn't contain.
+    fn encode_indexes(&self, contents: &mut String, format: &TextFormat) {
+        let stale = |key: &str| {
+            self.deferred_index_drops.as_ref().is_some_and(|drops| drops.contains(key))
+        };
+
+        let mut tagged: Vec<&str> = self.tags.tagged_keys().filter(|key| !stale(key)).collect();
+        tagged.sort_unstable();
+        for key in tagged {
+            for tag in self.tags.tags_of(key) {
+                contents.push_str(TAG_PREFIX);
+                push_fields(contents, &[key, tag], format);
+            }
+        }
+
+        let mut links: Vec<(&str, &str, &str)> = self
+            .links
+            .iter()
+            .filter(|(from, _, to)| !stale(from) && !stale(to))
+            .collect();
+        links.sort_unstable();
+        for (from, rel, to) in links {
+            contents.push_str(LINK_PREFIX);
+            push_fields(contents, &[from, rel, to], format);
+        }
+    }
+
+    /// Re-apply a `#@tag` or `#@link` line (`index` counts from 0)
+    fn restore_index_line(&mut self, index: usize, line: &str, format: &TextFormat) -> Result<()> {
+        let malformed = |msg: String| {
+            BurrowError::SerializationError(format!("line {}: {}", index + 1, msg))
+        };
+
+        if let Some(rest) = line.strip_prefix(TAG_PREFIX) {
+            let fields = parse_fields(rest, 2, format).map_err(malformed)?;
+            self.tag(&fields[0], &fields[1])?;
+        } else if let Some(rest) = line.strip_prefix(LINK_PREFIX) {
+            let fields = parse_fields(rest, 3, format).map_err(malformed)?;
+            self.link(&fields[0], &fields[1], &fields[2])?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse text-format contents, verifying the checksum trailer if present
+pub(crate) fn decode_text(contents: &str) -> Result<Vec<(String, Vec<u8>)>> {
+    records(checked_body(contents, true)?).collect()
+}
+
+/// The records before the checksum trailer, after verifying it if asked
+fn checked_body(contents: &str, verify: bool) -> Result<&str> {
+    let (body, checksum) = split_checksum(contents);
+
+    if verify {
+        if let Some(raw) = checksum {
+            check_trailer(raw, fnv1a64(body.as_bytes()))?;
+        }
+    }
+
+    Ok(body)
+}
+
+/// Check a file's checksum trailer, if it has one, without reading it whole
+///
+/// Matches `split_checksum`: the trailer is the last record that isn't
+/// empty, and hashes every byte before it.
+fn verify_file_checksum(path: &Path, format: &TextFormat) -> Result<()> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut line = Vec::new();
+    let mut hash = FNV1A64_START;
+    // The trailer seen last and the hash of everything before it
+    let mut trailer: Option<(String, u64)> = None;
+
+    while reader.read_until(format.record_separator as u8, &mut line)? > 0 {
+        let content = String::from_utf8_lossy(&line);
+        let content = content.trim_end_matches([format.record_separator, '\r']);
+        if !content.is_empty() {
+            trailer = content
+                .strip_prefix(CHECKSUM_PREFIX)
+                .map(|raw| (raw.to_string(), hash));
+        }
+        hash = fnv1a64_extend(hash, &line);
+        line.clear();
+    }
+
+    match trailer {
+        Some((raw, found)) => check_trailer(&raw, found),
+        None => Ok(()),
+    }
+}
+
+/// Compare a `#checksum` trailer's value with the hash of the body
+fn check_trailer(raw: &str, found: u64) -> Result<()> {
+    let expected = u64::from_str_radix(raw.trim(), 16).map_err(|_| {
+        BurrowError::SerializationError(format!("malformed checksum '{}'", raw))
+    })?;
+    if expected != found {
+        return Err(BurrowError::ChecksumMismatch { expected, found });
+    }
+    Ok(())
+}
+
+/// Parse each record line, skipping comments and blank lines
+fn records(body: &str) -> impl Iterator<Item = Result<(String, Vec<u8>)>> + '_ {
+    body.lines()
+        .enumerate()
+        .filter(|(_, line)| !is_comment_or_blank(line, &TextFormat::default()))
+        .map(|(index, line)| {
+            parse_line(line, &TextFormat::default()).map_err(|msg| {
+                BurrowError::SerializationError(format!("line {}: {}", index + 1, msg))
+            })
+        })
+}
+
+/// Each record in a text file, without its separator
+///
+/// With the default `\n` separator a trailing `\r` is dropped too, as
+/// `BufRead::lines` does, so files written on Windows still load.
+fn file_records(path: &Path, format: &TextFormat) -> Result<impl Iterator<Item = Result<String>>> {
+    let separator = format.record_separator as u8;
+    let strip_cr = format.record_separator == '\n';
+    let mut reader = BufReader::new(File::open(path)?);
+
+    Ok(std::iter::from_fn(move || {
+        let mut record = Vec::new();
+        match reader.read_until(separator, &mut record) {
+            Ok(0) => None,
+            Ok(_) => {
+                if record.last() == Some(&separator) {
+                    record.pop();
+                }
+                if strip_cr && record.last() == Some(&b'\r') {
+                    record.pop();
+                }
+                Some(String::from_utf8(record).map_err(|_| {
+                    BurrowError::IoError(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "stream did not contain valid UTF-8",
+                    ))
+                }))
+            }
+            Err(e) => Some(Err(e.into())),
+        }
+    }))
+}
+
+/// Replace `path` with `contents` without ever leaving a partial file
+///
+/// The data goes to a temporary file beside `path` (named per process, so
+/// concurrent savers don't share it) and is renamed over `path` once synced.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
+/// Whether a line carries no record
+///
+/// A record always contains a field separator, so a line of spaces followed
+/// by a tab is still a record (a whitespace key).
+fn is_comment_or_blank(line: &str, format: &TextFormat) -> bool {
+    line.starts_with('#') || (!line.contains(format.field_separator) && line.trim().is_empty())
+}
+
+/// Split a `key<TAB>value` line and unescape both halves
+fn parse_line(line: &str, format: &TextFormat) -> std::result::Result<(String, Vec<u8>), String> {
+    let (raw_key, raw_value) = line
+        .split_once(format.field_separator)
+        .ok_or_else(|| separator_name(format.field_separator, "missing"))?;
+    if raw_value.contains(format.field_separator) {
+        return Err(separator_name(format.field_separator, "unescaped"));
+    }
+
+    let key = String::from_utf8(unescape(raw_key)?)
+        .map_err(|_| "key is not valid UTF-8".to_string())?;
+    let value = unescape(raw_value)?;
+
+    Ok((key, value))
+}
+
+/// "missing tab separator" and the like, for parse errors
+fn separator_name(separator: char, problem: &str) -> String {
+    match separator {
+        '\t' => format!("{} tab separator", problem),
+        other => format!("{} separator {:?}", problem, other),
+    }
+}
+
+/// Append escaped `fields` joined by the field separator, ending the record
+fn push_fields(contents: &mut String, fields: &[&str], format: &TextFormat) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            contents.push(format.field_separator);
+        }
+        contents.push_str(&escape(field.as_bytes(), format));
+    }
+    contents.push(format.record_separator);
+}
+
+/// Split a tab-separated metadata line into exactly `count` unescaped fields
+fn parse_fields(
+    line: &str,
+    count: usize,
+    format: &TextFormat,
+) -> std::result::Result<Vec<String>, String> {
+    let fields = line
+        .split(format.field_separator)
+        .map(|raw| {
+            String::from_utf8(unescape(raw)?).map_err(|_| "field is not valid UTF-8".to_string())
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    if fields.len() != count {
+        return Err(format!("expected {} fields, found {}", count, fields.len()));
+    }
+    Ok(fields)
+}
+
+/// Separate the `#checksum` trailer (if any) from the records before it
+fn split_checksum(contents: &str) -> (&str, Option<&str>) {
+    let trimmed = contents.trim_end_matches(['\n', '\r']);
+    let last_line_start = trimmed.rfind('\n').map_or(0, |i| i + 1);
+
+    match trimmed[last_line_start..].strip_prefix(CHECKSUM_PREFIX) {
+        Some(checksum) => (&contents[..last_line_start], Some(checksum)),
+        None => (contents, None),
+    }
+}
+
+/// Escape a key, also protecting a leading `#`
+fn escape_key(key: &str, format: &TextFormat) -> String {
+    let escaped = escape(key.as_bytes(), format);
+    if escaped.starts_with('#') {
+        format!("\\{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// Escape bytes so they fit in one record with no separator inside
+fn escape(bytes: &[u8], format: &TextFormat) -> String {
+    let mut out = String::with_capacity(bytes.len());
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => {
+            for c in text.chars() {
+                escape_char(c, format, &mut out);
+            }
+        }
+        Err(_) => {
+            for &byte in bytes {
+                if byte.is_ascii() {
+                    escape_char(byte as char, format, &mut out);
+                } else {
+                    out.push_str(&format!("\\x{:02x}", byte));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+fn escape_char(c: char, format: &TextFormat, out: &mut String) {
+    match c {
+        '\\' => out.push_str("\\\\"),
+        '\t' => out.push_str("\\t"),
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        c if c.is_ascii_control()
+            || c == format.field_separator
+            || c == format.record_separator =>
+        {
+            out.push_str(&format!("\\x{:02x}", c as u32))
+        }
+        c => out.push(c),
+    }
+}
+
+/// Reverse `escape`
+fn unescape(text: &str) -> std::result::Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        match chars.next() {
+            Some('\\') => out.push(b'\\'),
+            Some('#') => out.push(b'#'),
+            Some('t') => out.push(b'\t'),
+            Some('n') => out.push(b'\n'),
+            Some('r') => out.push(b'\r'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = if hex.len() == 2 {
+                    u8::from_str_radix(&hex, 16).ok()
+                } else {
+                    None
+                };
+                out.push(byte.ok_or_else(|| format!("invalid escape \\x{}", hex))?);
+            }
+            Some(other) => return Err(format!("unknown escape \\{}", other)),
+            None => return Err("trailing backslash".to_string()),
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_escape_roundtrip() {
+        let values: [&[u8]; 4] = [
+            b"plain value",
+            b"tab\there\nnewline \\ backslash",
+            "unicode \u{1f980}".as_bytes(),
+            &[0xff, 0x00, b'a', 0x80],
+        ];
+
+        for value in values {
+            assert_eq!(unescape(&escape(value, &TextFormat::default())).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.txt");
+
+        let mut db = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        db.put_raw("user:1".to_string(), b"Alice\tSmith".to_vec()).unwrap();
+        db.put_raw("blob".to_string(), vec![0, 159, 146, 150]).unwrap();
+        db.save_to_file(&file).unwrap();
+
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100).unwrap();
+        assert_eq!(restored.load_from_file(&file).unwrap(), 2);
+        assert_eq!(restored.get("user:1").unwrap().unwrap(), b"Alice\tSmith");
+        assert_eq!(restored.get("blob").unwrap().unwrap(), vec![0, 159, 146, 150]);
+    }
+
+    #[test]
+    fn test_demoted_keys_keep_their_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.txt");
+
+        let mut db = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        for key in ["user:1", "docs/readme", "user_1"] {
+            db.put_raw(key.to_string(), key.as_bytes().to_vec()).unwrap();
+            db.demote(key).unwrap();
+        }
+        db.get("user:1").unwrap();
+        let mut keys = db.keys().unwrap();
+        keys.sort();
+        assert_eq!(keys, ["docs/readme", "user:1", "user_1"]);
+        db.save_to_file(&file).unwrap();
+
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100).unwrap();
+        assert_eq!(restored.load_from_file(&file).unwrap(), 3);
+        for key in ["user:1", "docs/readme", "user_1"] {
+            assert_eq!(restored.get(key).unwrap().unwrap(), key.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_tags_and_links_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.txt");
+
+        let mut db = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 1).unwrap();
+        for key in ["order1", "user\t1", "city1"] {
+            db.put_raw(key.to_string(), b"v".to_vec()).unwrap();
+        }
+        db.tag("order1", "open").unwrap();
+        db.tag("user\t1", "vip").unwrap();
+        db.tag("user\t1", "open").unwrap();
+        db.link("order1", "placed_by", "user\t1").unwrap();
+        db.link("user\t1", "lives in", "city1").unwrap();
+        db.save_to_file(&file).unwrap();
+
+        let saved = fs::read_to_string(&file).unwrap();
+        assert!(saved.contains("#@tag user\\t1\tvip\n"));
+        assert!(saved.contains("#@link order1\tplaced_by\tuser\\t1\n"));
+
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 1).unwrap();
+        assert_eq!(restored.load_from_file(&file).unwrap(), 3);
+        assert_eq!(restored.keys_with_tag("open"), vec!["order1", "user\t1"]);
+        assert_eq!(restored.tags_of("user\t1"), vec!["open", "vip"]);
+        assert_eq!(restored.find_linked_to("user\t1"), vec!["order1"]);
+        assert_eq!(restored.find_linked_to("city1"), vec!["user\t1"]);
+        restored.save_to_file(temp_dir.path().join("again.txt")).unwrap();
+        assert_eq!(fs::read_to_string(temp_dir.path().join("again.txt")).unwrap(), saved);
+    }
+
+    #[test]
+    fn test_load_reports_progress() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.txt");
+
+        let mut db = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        for i in 0..25 {
+            db.put_raw(format!("key-{}", i), b"v".to_vec()).unwrap();
+        }
+        db.save_to_file(&file).unwrap();
+
+        let mut counts = Vec::new();
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100).unwrap();
+        let loaded = restored
+            .load_from_file_with_progress(&file, 10, |count| counts.push(count))
+            .unwrap();
+        assert_eq!(loaded, 25);
+        assert_eq!(counts, vec![10, 20]);
+    }
+
+    #[test]
+    fn test_verify_save_file_lists_every_problem() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.txt");
+        let mut db = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        db.put_raw("a".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("b".to_string(), b"2".to_vec()).unwrap();
+        db.tag("a", "t").unwrap();
+        db.link("a", "r", "b").unwrap();
+        db.save_to_file(&file).unwrap();
+        assert_eq!(BurrowDB::verify_save_file(&file).unwrap(), 2);
+
+        fs::write(&file, "a\t1\nno tab here\na\t2\n#@tag a\tt\n#@link a\tr\tghost\nbad\\q\tv\n").unwrap();
+        match BurrowDB::verify_save_file(&file) {
+            Err(BurrowError::SerializationError(msg)) => assert_eq!(
+                msg,
+                "line 2: missing tab separator; line 5: names missing key 'ghost'; \
+                 line 6: unknown escape \\q"
+            ),
+            other => panic!("expected a serialization error, got {:?}", other),
+        }
+        assert!(BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100)
+            .unwrap()
+            .load_from_file(&file)
+            .is_err());
+    }
+
+    #[test]
+    fn test_checksum_detects_corruption() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.txt");
+
+        let mut db = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        db.put_raw("#checksum 0".to_string(), b"looks like a trailer".to_vec()).unwrap();
+        db.put_raw("balance".to_string(), b"100".to_vec()).unwrap();
+        db.save_to_file(&file).unwrap();
+
+        let saved = fs::read_to_string(&file).unwrap();
+        fs::write(&file, saved.replace("100", "900")).unwrap();
+
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100).unwrap();
+        assert!(matches!(
+            restored.load_from_file(&file),
+            Err(BurrowError::ChecksumMismatch { .. })
+        ));
+        assert_eq!(restored.load_from_file_unchecked(&file).unwrap(), 2);
+        assert_eq!(restored.get("#checksum 0").unwrap().unwrap(), b"looks like a trailer");
+    }
+
+    #[test]
+    fn test_comments_and_blank_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("annotated.txt");
+        fs::write(&file, "# users\n\nuser:1\tAlice\n  \n\\#tag\tliteral\n \t\n").unwrap();
+
+        let mut db = BurrowDB::with_config(temp_dir.path().join("db").to_str().unwrap(), 100).unwrap();
+        assert_eq!(db.load_from_file(&file).unwrap(), 3);
+        assert_eq!(db.get("#tag").unwrap().unwrap(), b"literal");
+        assert_eq!(db.get(" ").unwrap().unwrap(), b"");
+    }
+
+    #[test]
+    fn test_header_is_skipped_on_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.txt");
+
+        let mut db = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        db.put_raw("k".to_string(), b"v".to_vec()).unwrap();
+        let format = TextFormat { header: true, ..TextFormat::default() };
+        db.save_to_file_with(&file, &format).unwrap();
+        assert!(fs::read_to_string(&file).unwrap().starts_with("# generated at "));
+
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100).unwrap();
+        assert_eq!(restored.load_from_file(&file).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_custom_separators_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.txt");
+        let format = TextFormat {
+            field_separator: ',',
+            record_separator: '\0',
+            ..TextFormat::default()
+        };
+
+        let mut db = BurrowDB::with_config(temp_dir.path().join("a").to_str().unwrap(), 100).unwrap();
+        db.put_raw("a,b".to_string(), b"line 1\nline 2".to_vec()).unwrap();
+        db.put_raw("csv".to_string(), b"x,y\0z".to_vec()).unwrap();
+        db.tag("a,b", "multi,line").unwrap();
+        db.save_to_file_with(&file, &format).unwrap();
+
+        let saved = fs::read_to_string(&file).unwrap();
+        assert!(saved.starts_with("a\\x2cb,line 1\\nline 2\0csv,x\\x2cy\\x00z\0"));
+        assert!(saved.contains("#@tag a\\x2cb,multi\\x2cline\0"));
+        assert_eq!(BurrowDB::verify_save_file_with(&file, &format).unwrap(), 2);
+
+        let mut restored = BurrowDB::with_config(temp_dir.path().join("b").to_str().unwrap(), 100).unwrap();
+        assert_eq!(restored.load_from_file_with(&file, &format).unwrap(), 2);
+        assert_eq!(restored.get("a,b").unwrap().unwrap(), b"line 1\nline 2");
+        assert_eq!(restored.get("csv").unwrap().unwrap(), b"x,y\0z");
+        assert_eq!(restored.tags_of("a,b"), vec!["multi,line"]);
+
+        fs::write(&file, "k,v,extra\0").unwrap();
+        match restored.load_from_file_with(&file, &format) {
+            Err(BurrowError::SerializationError(msg)) => {
+                assert_eq!(msg, "line 1: unescaped separator ','")
+            }
+            other => panic!("expected a serialization error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_separators_are_validated() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        let file = temp_dir.path().join("data.txt");
+
+        let invalid = [('x', '\n'), ('\\', '\n'), ('#', '\n'), (';', ';')];
+        for (field_separator, record_separator) in invalid {
+            let format = TextFormat { field_separator, record_separator, ..TextFormat::default() };
+            assert!(matches!(
+                db.save_to_file_with(&file, &format),
+                Err(BurrowError::InvalidConfig(_))
+            ));
+        }
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn test_file_without_checksum_loads() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("legacy.txt");
+        fs::write(&file, "a\t1\nb\t2\n").unwrap();
+
+        let mut db = BurrowDB::with_config(temp_dir.path().join("db").to_str().unwrap(), 100).unwrap();
+        assert_eq!(db.load_from_file(&file).unwrap(), 2);
+    }
+}
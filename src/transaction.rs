@@ -0,0 +1,208 @@
+//! Buffered multi-key writes that apply all together or not at all
+
+use crate::document_block::DocumentBlock;
+use crate::error::{BurrowError, Result};
+use crate::BurrowDB;
+use std::collections::HashMap;
+
+/// A staged write
+enum Op {
+    /// Store a value; `true` if it may overwrite under overwrite protection
+    Put(String, Vec<u8>, bool),
+    Delete(String),
+}
+
+impl Op {
+    fn key(&self) -> &str {
+        match self {
+            Op::Put(key, ..) | Op::Delete(key) => key,
+        }
+    }
+}
+
+/// Writes buffered against a database until `commit`
+///
+/// Nothing touches the database until `commit`. Dropping the transaction,
+/// or calling `rollback`, discards the buffered writes.
+///
+/// ```no_run
+/// use burrow_db::BurrowDB;
+///
+/// let mut db = BurrowDB::new()?;
+/// let mut tx = db.begin();
+/// tx.put("account:1", b"90".to_vec());
+/// tx.put("account:2", b"110".to_vec());
+/// tx.commit()?;
+/// # Ok::<(), burrow_db::BurrowError>(())
+/// ```
+#[must_use = "a transaction does nothing unless committed"]
+pub struct Transaction<'a> {
+    db: &'a mut BurrowDB,
+    ops: Vec<Op>,
+}
+
+impl BurrowDB {
+    /// Start buffering writes to apply atomically
+    pub fn begin(&mut self) -> Transaction<'_> {
+        Transaction { db: self, ops: Vec::new() }
+    }
+}
+
+impl Transaction<'_> {
+    /// Stage storing raw bytes under `key`
+    pub fn put(&mut self, key: &str, data: Vec<u8>) {
+        self.ops.push(Op::Put(key.to_string(), data, false));
+    }
+
+    /// Stage storing raw bytes under `key`, overwriting even with
+    /// overwrite protection on
+    pub fn put_force(&mut self, key: &str, data: Vec<u8>) {
+        self.ops.push(Op::Put(key.to_string(), data, true));
+    }
+
+    /// Stage deleting `key`
+    pub fn delete(&mut self, key: &str) {
+        self.ops.push(Op::Delete(key.to_string()));
+    }
+
+    /// Number of staged writes
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Whether nothing has been staged
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Apply every staged write in order
+    ///
+    /// All puts are checked against the size limits and validator first, so a
+    /// rejected value means nothing is applied. Under overwrite protection a
+    /// put onto a key that exists, or that an earlier staged put created,
+    /// fails the same way with `KeyExists`; staging a delete first frees the
+    /// key. If a write then fails partway (an I/O error from the cold tier),
+    /// the keys already written are restored to their previous values before
+    /// the error is returned.
+    pub fn commit(self) -> Result<()> {
+        let db = self.db;
+
+        // Whether each key exists once the ops before the current one apply
+        let mut staged: HashMap<String, bool> = HashMap::new();
+        for op in &self.ops {
+            let key = db.normalize_key(op.key()).into_owned();
+            if let Op::Put(_, data, force) = op {
+                if !force {
+                    match staged.get(&key) {
+                        Some(true) if db.overwrite_protection => {
+                            return Err(BurrowError::KeyExists(key));
+                        }
+                        Some(_) => {}
+                        None => db.check_overwrite(&key)?,
+                    }
+                }
+                db.check_write(&key, data)?;
+            }
+            staged.insert(key, matches!(op, Op::Put(..)));
+        }
+
+        let mut undo = Vec::with_capacity(self.ops.len());
+        for op in self.ops {
+            let before = match db.peek(op.key()) {
+                Ok(before) => before,
+                Err(e) => return Err(restore(db, undo, e)),
+            };
+            let key = op.key().to_string();
+
+            let result = match op {
+                Op::Put(key, data, _) => db.store(key, DocumentBlock::from_raw(data)),
+                Op::Delete(key) => db.delete(&key),
+            };
+            undo.push((key, before));
+
+            if let Err(e) = result {
+                return Err(restore(db, undo, e));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Discard every staged write
+    pub fn rollback(self) {}
+}
+
+/// Best-effort undo of a partly applied commit, passing the original error through
+fn restore(
+    db: &mut BurrowDB,
+    undo: Vec<(String, Option<Vec<u8>>)>,
+    error: BurrowError,
+) -> BurrowError {
+    for (key, before) in undo.into_iter().rev() {
+        let _ = match before {
+            Some(data) => db.store(key, DocumentBlock::from_raw(data)),
+            None => db.delete(&key),
+        };
+    }
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_commit_applies_all_or_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100)
+            .unwrap()
+            .with_max_value_size(4);
+        db.put_raw("a".to_string(), b"1".to_vec()).unwrap();
+
+        let mut tx = db.begin();
+        tx.put("b", b"2".to_vec());
+        tx.delete("a");
+        tx.put("c", b"too long".to_vec());
+        assert!(matches!(tx.commit(), Err(BurrowError::ValueTooLarge { .. })));
+        assert_eq!(db.keys().unwrap(), vec!["a".to_string()]);
+
+        let mut tx = db.begin();
+        tx.put("b", b"2".to_vec());
+        tx.rollback();
+        assert!(!db.contains_key("b"));
+
+        let mut tx = db.begin();
+        tx.put("b", b"2".to_vec());
+        tx.delete("a");
+        tx.commit().unwrap();
+        assert_eq!(db.keys().unwrap(), vec!["b".to_string()]);
+    }
+
+    #[test]
+    fn test_commit_respects_overwrite_protection() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100)
+            .unwrap()
+            .with_overwrite_protection(true);
+        db.put_raw("a".to_string(), b"1".to_vec()).unwrap();
+
+        let mut tx = db.begin();
+        tx.put("b", b"2".to_vec());
+        tx.put("a", b"2".to_vec());
+        assert!(matches!(tx.commit(), Err(BurrowError::KeyExists(key)) if key == "a"));
+        assert_eq!(db.keys().unwrap(), vec!["a".to_string()]);
+
+        let mut tx = db.begin();
+        tx.put("b", b"2".to_vec());
+        tx.put("b", b"3".to_vec());
+        assert!(matches!(tx.commit(), Err(BurrowError::KeyExists(key)) if key == "b"));
+
+        let mut tx = db.begin();
+        tx.delete("a");
+        tx.put("a", b"2".to_vec());
+        tx.put_force("a", b"3".to_vec());
+        tx.commit().unwrap();
+        assert_eq!(db.get("a").unwrap().unwrap(), b"3");
+    }
+}
@@ -0,0 +1,81 @@
+//! Named transforms applied to values on read
+//!
+//! A transform computes a derived view of a value (upper-cased, its length,
+//! ...) without changing what is stored. Values are read as UTF-8 text,
+//! with invalid bytes replaced by U+FFFD.
+
+use crate::error::{BurrowError, Result};
+use crate::BurrowDB;
+use std::collections::HashMap;
+
+/// A function from a stored value to its transformed view
+///
+/// Transforms must be `Send + Sync` so the database can be shared between threads.
+pub type Transform = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+/// The transforms every database starts with
+pub(crate) fn builtin_transforms() -> HashMap<String, Transform> {
+    let builtins: [(&str, Transform); 4] = [
+        ("upper", Box::new(|value| value.to_uppercase())),
+        ("lower", Box::new(|value| value.to_lowercase())),
+        ("reverse", Box::new(|value| value.chars().rev().collect())),
+        ("len", Box::new(|value| value.chars().count().to_string())),
+    ];
+
+    builtins
+        .into_iter()
+        .map(|(name, f)| (name.to_string(), f))
+        .collect()
+}
+
+impl BurrowDB {
+    /// Register `f` under `name`, replacing any transform of that name
+    pub fn register_transform(&mut self, name: &str, f: Transform) {
+        self.transforms.insert(name.to_string(), f);
+    }
+
+    /// Names of the registered transforms, sorted
+    pub fn transform_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.transforms.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Read `key` through the transform called `transform`
+    ///
+    /// Returns `None` if the key doesn't exist and `InvalidConfig` if no
+    /// transform has that name. Like `keys_with_value`, this reads without
+    /// promoting the document or counting an access.
+    pub fn get_transformed(&self, key: &str, transform: &str) -> Result<Option<String>> {
+        let f = self.transforms.get(transform).ok_or_else(|| {
+            BurrowError::InvalidConfig(format!("unknown transform '{}'", transform))
+        })?;
+
+        Ok(self
+            .peek(key)?
+            .map(|value| f(&String::from_utf8_lossy(&value))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_transforms_leave_value_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.put_raw("name".to_string(), "Crème".as_bytes().to_vec()).unwrap();
+        db.register_transform("shout", Box::new(|value| format!("{}!", value.to_uppercase())));
+
+        assert_eq!(db.get_transformed("name", "upper").unwrap().unwrap(), "CRÈME");
+        assert_eq!(db.get_transformed("name", "reverse").unwrap().unwrap(), "emèrC");
+        assert_eq!(db.get_transformed("name", "len").unwrap().unwrap(), "5");
+        assert_eq!(db.get_transformed("name", "shout").unwrap().unwrap(), "CRÈME!");
+        assert_eq!(db.get_transformed("missing", "upper").unwrap(), None);
+        assert!(db.get_transformed("name", "nope").is_err());
+
+        assert_eq!(db.get("name").unwrap().unwrap(), "Crème".as_bytes());
+    }
+}
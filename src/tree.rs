@@ -0,0 +1,84 @@
+//! Hierarchical view of namespaced keys
+
+use crate::error::Result;
+use crate::BurrowDB;
+use std::collections::BTreeMap;
+
+/// One path segment, with the value of the key that ends here (if any)
+#[derive(Default)]
+struct Node {
+    value: Option<Vec<u8>>,
+    children: BTreeMap<String, Node>,
+}
+
+impl BurrowDB {
+    /// Render every key as an indented tree, splitting keys on `separator`
+    ///
+    /// Each segment is one line, indented two spaces per level and sorted
+    /// within its parent. A segment that ends a key shows the value inline
+    /// as `name = value`; that includes keys that are also a prefix of
+    /// others, such as `a` beside `a/b`. Control characters in values are
+    /// shown as spaces so each node stays on one line.
+    pub fn tree(&self, separator: char) -> Result<String> {
+        let mut root = Node::default();
+        for (key, value) in self.entries()? {
+            let node = key
+                .split(separator)
+                .fold(&mut root, |node, segment| node.children.entry(segment.to_string()).or_default());
+            node.value = Some(value);
+        }
+
+        let mut out = String::new();
+        render(&root, 0, &mut out);
+        Ok(out)
+    }
+}
+
+fn render(node: &Node, depth: usize, out: &mut String) {
+    for (segment, child) in &node.children {
+        out.push_str(&"  ".repeat(depth));
+        out.push_str(segment);
+        if let Some(value) = &child.value {
+            let shown: String = String::from_utf8_lossy(value)
+                .chars()
+                .map(|c| if c.is_control() { ' ' } else { c })
+                .collect();
+            out.push_str(" = ");
+            out.push_str(&shown);
+        }
+        out.push('\n');
+        render(child, depth + 1, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tree_nests_by_separator() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        for (key, value) in [
+            ("users.2.name", "Bob"),
+            ("users.1.name", "Alice"),
+            ("users.1", "admin"),
+            ("users.1.email", "a@x\ny"),
+            ("config", "on"),
+        ] {
+            db.put_raw(key.to_string(), value.as_bytes().to_vec()).unwrap();
+        }
+
+        assert_eq!(db.tree('.').unwrap(), "\
+config = on
+users
+  1 = admin
+    email = a@x y
+    name = Alice
+  2
+    name = Bob
+");
+        assert_eq!(db.tree('/').unwrap().lines().count(), 5);
+    }
+}
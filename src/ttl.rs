@@ -0,0 +1,359 @@
+//! Expiring keys
+//!
+//! Expiry times are kept in memory beside the documents. An expired key is
+//! removed lazily the next time `get` reaches it, or all at once by
+//! `flush_expired`; either way an `Event::Expired` is emitted. Storing a new
+//! value clears any expiry, as does deleting.
+
+use crate::audit::AuditOp;
+use crate::error::Result;
+use crate::events::Event;
+use crate::rng::Rng;
+use crate::BurrowDB;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+impl BurrowDB {
+    /// Expire `key` once `ttl` has passed
+    ///
+    /// Replaces any earlier expiry. `touch` restarts the full `ttl`.
+    /// Returns `false` if the key doesn't exist.
+    pub fn expire(&mut self, key: &str, ttl: Duration) -> bool {
+        let key = self.normalize_owned_key(key.to_string());
+        if !self.contains_key(&key) {
+            return false;
+        }
+
+        self.expires_at.insert(key.clone(), SystemTime::now() + ttl);
+        self.ttl_lengths.insert(key, ttl);
+        true
+    }
+
+    /// Expire every key matching a glob pattern once `ttl` has passed
+    ///
+    /// Matches as `keys_matching` does and replaces any earlier expiry.
+    /// Keys that have already expired are left to be removed rather than
+    /// given a new lease. Returns how many keys were given the expiry.
+    pub fn expire_matching(&mut self, pattern: &str, ttl: Duration) -> Result<usize> {
+        self.expire_matching_with_jitter(pattern, ttl, Duration::ZERO, 0)
+    }
+
+    /// Like `expire_matching`, but spread the expiry times over `ttl ± jitter`
+    ///
+    /// Keys given one expiry time all come due together, so the `get`s or
+    /// `flush_expired` after it pay for removing every one of them at once.
+    /// Moving each key's expiry by a random amount of up to `jitter` either
+    /// way spreads that work evenly over a window twice as wide. Offsets
+    /// come from the crate's deterministic generator, so the same `seed`
+    /// over the same keys always spreads them the same way. Expiry times
+    /// that would fall before now are moved up to now. `touch` restarts a
+    /// key's own jittered TTL, so the spread survives keep-alives.
+    pub fn expire_matching_with_jitter(
+        &mut self,
+        pattern: &str,
+        ttl: Duration,
+        jitter: Duration,
+        seed: u64,
+    ) -> Result<usize> {
+        let now = SystemTime::now();
+        let latest = now + ttl + jitter;
+        let earliest = (now + ttl).checked_sub(jitter).map_or(now, |earliest| earliest.max(now));
+        let window = latest.duration_since(earliest).unwrap_or_default();
+        let mut rng = Rng::new(seed);
+
+        let mut count = 0;
+        // keys_matching sorts, so each key draws the same offset every time
+        for key in self.keys_matching(pattern)? {
+            if !self.is_expired(&key) {
+                let unit = rng.next_u64() as f64 / u64::MAX as f64;
+                let deadline = earliest + window.mul_f64(unit);
+                let length = deadline.duration_since(now).unwrap_or_default();
+                self.ttl_lengths.insert(key.clone(), length);
+                self.expires_at.insert(key, deadline);
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Store `value` at `key`, expiring at `epoch_secs` (Unix seconds)
+    ///
+    /// A time that has already passed means the value would be expired the
+    /// moment it was stored, so it is not stored and any current value at
+    /// `key` is deleted instead; this returns `false` in that case. The
+    /// deadline is fixed: `touch` doesn't move it.
+    pub fn put_with_expiry_at(&mut self, key: String, value: Vec<u8>, epoch_secs: u64) -> Result<bool> {
+        self.check_overwrite(&key)?;
+        let deadline = UNIX_EPOCH + Duration::from_secs(epoch_secs);
+        if deadline <= SystemTime::now() {
+            self.delete(&key)?;
+            return Ok(false);
+        }
+
+        let key = self.normalize_owned_key(key);
+        self.put_raw(key.clone(), value)?;
+        self.expires_at.insert(key, deadline);
+        Ok(true)
+    }
+
+    /// Time left before `key` expires
+    ///
+    /// `None` if the key has no expiry, doesn't exist, or has already
+    /// expired but not yet been removed.
+    pub fn ttl(&self, key: &str) -> Option<Duration> {
+        let deadline = self.expires_at.get(&*self.normalize_key(key))?;
+        deadline.duration_since(SystemTime::now()).ok()
+    }
+
+    /// Remove the expiry from `key`, making it permanent
+    ///
+    /// Returns whether there was an expiry to remove. A key that has already
+    /// expired stays expired.
+    pub fn persist(&mut self, key: &str) -> bool {
+        let key = &*self.normalize_key(key);
+        if self.is_expired(key) {
+            return false;
+        }
+        self.ttl_lengths.remove(key);
+        self.expires_at.remove(key).is_some()
+    }
+
+    /// Keys that have expired but not been removed yet, sorted
+    pub fn expired_keys(&self) -> Vec<String> {
+        let now = SystemTime::now();
+        let mut expired: Vec<String> = self
+            .expires_at
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        expired.sort_unstable();
+        expired
+    }
+
+    /// Delete every expired key now, returning how many were removed
+    pub fn flush_expired(&mut self) -> Result<usize> {
+        let expired = self.expired_keys();
+        for key in &expired {
+            self.remove_expired(key)?;
+        }
+        Ok(expired.len())
+    }
+
+    /// Delete an expired normalized key and announce it
+    ///
+    /// Removing the key also clears its expiry, so whichever of the lazy
+    /// check and `flush_expired` gets there first is the only one to emit.
+    pub(crate) fn remove_expired(&mut self, key: &str) -> Result<()> {
+        let value = self.peek(key)?;
+        self.discard(key)?;
+
+        if let Some(value) = value {
+            self.audit(AuditOp::Expire, key);
+            self.emit(Event::Expired { key: key.to_string(), value });
+        }
+        Ok(())
+    }
+
+    /// Give a live key that was touched the full length of its TTL again
+    ///
+    /// Only TTLs set by `expire` or `expire_matching` have a length to
+    /// restart; a fixed deadline from `put_with_expiry_at` is left alone.
+    pub(crate) fn restart_ttl(&mut self, key: &str) {
+        let ttl = self.ttl_lengths.get(key);
+        if let (Some(deadline), Some(ttl)) = (self.expires_at.get_mut(key), ttl) {
+            *deadline = SystemTime::now() + *ttl;
+        }
+    }
+
+    /// The expiry of a normalized key, to carry across a rewrite with
+    /// `restore_expiry`, since storing a value clears it
+    pub(crate) fn save_expiry(&self, key: &str) -> Option<SavedExpiry> {
+        let deadline = *self.expires_at.get(key)?;
+        Some(SavedExpiry { deadline, ttl: self.ttl_lengths.get(key).copied() })
+    }
+
+    /// Put back an expiry taken by `save_expiry`, possibly under a new key
+    pub(crate) fn restore_expiry(&mut self, key: &str, saved: Option<SavedExpiry>) {
+        let Some(saved) = saved else {
+            return;
+        };
+        self.expires_at.insert(key.to_string(), saved.deadline);
+        if let Some(ttl) = saved.ttl {
+            self.ttl_lengths.insert(key.to_string(), ttl);
+        }
+    }
+
+    /// Forget a normalized key's expiry and TTL length
+    pub(crate) fn clear_expiry(&mut self, key: &str) {
+        self.expires_at.remove(key);
+        self.ttl_lengths.remove(key);
+    }
+
+    /// Whether a normalized key has passed its expiry time
+    pub(crate) fn is_expired(&self, key: &str) -> bool {
+        self.expires_at
+            .get(key)
+            .is_some_and(|deadline| *deadline <= SystemTime::now())
+    }
+}
+
+/// A key's expiry as `save_expiry` found it
+pub(crate) struct SavedExpiry {
+    deadline: SystemTime,
+    ttl: Option<Duration>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_expiry_is_lazy_and_reapable() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        for key in ["a", "b", "c"] {
+            db.put_raw(key.to_string(), b"1".to_vec()).unwrap();
+        }
+
+        assert!(db.expire("a", Duration::ZERO));
+        assert!(db.expire("b", Duration::ZERO));
+        assert!(db.expire("c", Duration::from_secs(3600)));
+        assert!(!db.expire("missing", Duration::ZERO));
+
+        assert_eq!(db.get("a").unwrap(), None);
+        assert_eq!(db.flush_expired().unwrap(), 1);
+        assert!(!db.contains_key("b"));
+        assert!(db.ttl("c").is_some());
+
+        db.put_raw("c".to_string(), b"2".to_vec()).unwrap();
+        assert_eq!(db.ttl("c"), None);
+    }
+
+    #[test]
+    fn test_expired_event_fires_once() {
+        use std::sync::{Arc, Mutex};
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.put_raw("lazy".to_string(), b"1".to_vec()).unwrap();
+        db.put_raw("swept".to_string(), b"2".to_vec()).unwrap();
+        db.expire("lazy", Duration::ZERO);
+        db.expire("swept", Duration::ZERO);
+
+        // Subscribed after the puts, so only expiry is seen; it is not
+        // also reported as a delete
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        db.subscribe(Box::new(move |event| sink.lock().unwrap().push(event.clone())));
+
+        assert_eq!(db.get("lazy").unwrap(), None);
+        assert_eq!(db.flush_expired().unwrap(), 1);
+        assert_eq!(db.get("swept").unwrap(), None);
+        assert_eq!(db.flush_expired().unwrap(), 0);
+
+        assert_eq!(*seen.lock().unwrap(), vec![
+            Event::Expired { key: "lazy".to_string(), value: b"1".to_vec() },
+            Event::Expired { key: "swept".to_string(), value: b"2".to_vec() },
+        ]);
+    }
+
+    #[test]
+    fn test_persist_removes_expiry() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.put_raw("session".to_string(), b"1".to_vec()).unwrap();
+
+        assert!(!db.persist("session"));
+        db.expire("session", Duration::from_secs(60));
+        assert!(db.ttl("session").unwrap() <= Duration::from_secs(60));
+        assert!(db.persist("session"));
+        assert_eq!(db.ttl("session"), None);
+
+        db.expire("session", Duration::ZERO);
+        assert!(!db.persist("session"));
+        assert_eq!(db.get("session").unwrap(), None);
+    }
+
+    #[test]
+    fn test_expired_key_is_not_contained() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        let mut db = db.with_insertion_order().unwrap();
+        db.put_raw("session".to_string(), b"1".to_vec()).unwrap();
+        db.expire("session", Duration::ZERO);
+
+        // Logically gone but still stored until something reaps it
+        assert!(!db.contains_key("session"));
+        assert_eq!(db.expired_keys(), vec!["session".to_string()]);
+        assert!(db.tag("session", "t").is_err());
+
+        db.put_raw("session".to_string(), b"2".to_vec()).unwrap();
+        assert!(db.contains_key("session"));
+        assert_eq!(db.keys().unwrap(), vec!["session".to_string()]);
+    }
+
+    #[test]
+    fn test_expire_matching() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        for key in ["cache:a", "cache:b", "cache:gone", "user:1"] {
+            db.put_raw(key.to_string(), b"1".to_vec()).unwrap();
+        }
+        db.expire("cache:gone", Duration::ZERO);
+
+        assert_eq!(db.expire_matching("cache:*", Duration::from_secs(60)).unwrap(), 2);
+        assert!(db.ttl("cache:a").is_some());
+        assert!(db.ttl("cache:b").is_some());
+        assert_eq!(db.ttl("user:1"), None);
+        assert_eq!(db.get("cache:gone").unwrap(), None);
+        assert_eq!(db.expire_matching("nothing*", Duration::ZERO).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_expire_matching_with_jitter_spreads_expiry() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        for i in 0..50 {
+            db.put_raw(format!("cache:{}", i), b"1".to_vec()).unwrap();
+        }
+
+        let ttl = Duration::from_secs(600);
+        let jitter = Duration::from_secs(60);
+        assert_eq!(db.expire_matching_with_jitter("cache:*", ttl, jitter, 7).unwrap(), 50);
+        let ttls = |db: &BurrowDB| -> Vec<Option<Duration>> {
+            (0..50).map(|i| db.ttl(&format!("cache:{}", i))).collect()
+        };
+        let spread: Vec<Duration> = ttls(&db).into_iter().map(Option::unwrap).collect();
+        let slack = Duration::from_secs(5);
+        assert!(spread.iter().all(|&left| left <= ttl + jitter && left + slack >= ttl - jitter));
+        assert!(spread.iter().any(|&left| left < ttl) && spread.iter().any(|&left| left > ttl));
+
+        // The same seed spreads the keys the same way
+        db.expire_matching_with_jitter("cache:*", ttl, jitter, 7).unwrap();
+        let again: Vec<bool> = ttls(&db).into_iter().map(|left| left.unwrap() > ttl).collect();
+        assert_eq!(again, spread.iter().map(|&left| left > ttl).collect::<Vec<_>>());
+
+        // With the jitter longer than the TTL, the early half is moved up
+        // to now rather than into the past
+        db.expire_matching_with_jitter("cache:*", Duration::ZERO, jitter, 1).unwrap();
+        assert!(ttls(&db).into_iter().flatten().all(|left| left <= jitter));
+        assert!(db.expired_keys().len() < 50);
+    }
+
+    #[test]
+    fn test_put_with_expiry_at() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        assert!(db.put_with_expiry_at("later".to_string(), b"1".to_vec(), now + 60).unwrap());
+        let left = db.ttl("later").unwrap();
+        assert!(left > Duration::from_secs(55) && left <= Duration::from_secs(60));
+
+        db.put_raw("gone".to_string(), b"old".to_vec()).unwrap();
+        assert!(!db.put_with_expiry_at("gone".to_string(), b"new".to_vec(), now - 1).unwrap());
+        assert_eq!(db.get("gone").unwrap(), None);
+    }
+}
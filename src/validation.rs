@@ -0,0 +1,61 @@
+//! Custom write validation
+//!
+//! A validator sees every write before it is applied: `put`, `put_raw`,
+//! `modify`, `bulk_load`, loads from save files, transaction commits and
+//! renames. Rejected writes change nothing and fail with `ValidationFailed`
+//! carrying the validator's message.
+
+use crate::BurrowDB;
+
+/// A check registered with `set_validator`
+///
+/// Called with the (normalized) key and the value about to be stored.
+/// Validators must be `Send + Sync` so the database can be shared between threads.
+pub type Validator = Box<dyn Fn(&str, &[u8]) -> Result<(), String> + Send + Sync>;
+
+impl BurrowDB {
+    /// Run `validator` before every write, replacing any earlier one
+    pub fn set_validator(&mut self, validator: Validator) {
+        self.validator = Some(validator);
+    }
+
+    /// Remove the validator, so writes are only checked against size limits
+    pub fn clear_validator(&mut self) {
+        self.validator = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BurrowError;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_validator_rejects_and_clears() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = BurrowDB::with_config(temp_dir.path().to_str().unwrap(), 100).unwrap();
+        db.set_validator(Box::new(|key, value| {
+            if key.contains(' ') {
+                return Err(format!("key '{}' contains a space", key));
+            }
+            if value.len() > 3 {
+                return Err("value longer than 3 bytes".to_string());
+            }
+            Ok(())
+        }));
+
+        assert!(matches!(
+            db.put_raw("has space".to_string(), b"1".to_vec()),
+            Err(BurrowError::ValidationFailed(msg)) if msg == "key 'has space' contains a space"
+        ));
+        db.put_raw("ok".to_string(), b"abc".to_vec()).unwrap();
+        assert!(db.modify("ok", |v| v.push(b'd')).is_err());
+        assert_eq!(db.get("ok").unwrap().unwrap(), b"abc");
+        assert!(db.rename_prefix("o", "o o").is_err());
+        assert_eq!(db.keys().unwrap(), vec!["ok"]);
+
+        db.clear_validator();
+        db.put_raw("has space".to_string(), b"long value".to_vec()).unwrap();
+    }
+}